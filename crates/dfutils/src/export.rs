@@ -0,0 +1,131 @@
+//! Writing grids as images, for the tools which composite the shapes outside of the runner
+
+use crate::grid::Grid;
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    io::{self, Seek, Write},
+};
+
+/// Write the masks of `grid`, as given by [Grid::data_inside], as an indexed PNG
+///
+/// Pixels outside of every shape get the transparent index 0, and the pixels of the n-th item
+/// met going row by row get index n, with a color of its own. Returns the items by index, minus
+/// one, for there can't be more than 255 of them.
+pub fn write_indexed_png<I: Copy + Eq + Hash, W: Write>(
+    grid: &Grid<Option<I>>,
+    w: W,
+) -> io::Result<Vec<I>> {
+    let mut items = vec![];
+    let mut indices = HashMap::new();
+    let mut pixels = Vec::with_capacity(grid.buffer.len());
+    for id in &grid.buffer {
+        let index = match id {
+            None => 0,
+            Some(id) => *indices.entry(*id).or_insert_with(|| {
+                items.push(*id);
+                items.len()
+            }),
+        };
+        if index > u8::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "more than 255 items in an indexed image",
+            ));
+        }
+        pixels.push(index as u8);
+    }
+
+    let mut palette = vec![0; 3];
+    for i in 1..=items.len() {
+        palette.extend_from_slice(&color(i));
+    }
+    let mut encoder = png::Encoder::new(w, grid.w as u32, grid.h as u32);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(palette);
+    // only the first entry is transparent
+    encoder.set_trns(vec![0]);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&pixels)?;
+    Ok(items)
+}
+
+/// Distinct colors for the first indices, with hues a golden angle apart
+fn color(i: usize) -> [u8; 3] {
+    let hue = (i as f32 * 0.618_034).fract() * 6.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    let (r, g, b) = match hue as usize {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    [r, g, b].map(|c| (55.0 + 200.0 * c) as u8)
+}
+
+/// Write the distances of `grid`, as given by [Grid::distances], as a single channel 32-bit
+/// float TIFF, row by row from the top
+pub fn write_float_tiff<W: Write + Seek>(grid: &Grid<f32>, w: W) -> io::Result<()> {
+    let mut encoder = tiff::encoder::TiffEncoder::new(w).map_err(io::Error::other)?;
+    encoder
+        .write_image::<tiff::encoder::colortype::Gray32Float>(
+            grid.w as u32,
+            grid.h as u32,
+            &grid.buffer,
+        )
+        .map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::Disk;
+
+    #[test]
+    fn export() {
+        use std::io::Cursor;
+        const W: usize = 40;
+        const H: usize = 30;
+
+        // items 7 on the left and 3 on the right, with nothing in between
+        let mut ids = Grid::new(W, H);
+        for y in 0..H {
+            for x in 0..W {
+                ids.set(x, y, [Some(7), None, Some(3)][3 * x / W]);
+            }
+        }
+        let mut png = vec![];
+        let items = write_indexed_png(&ids, &mut png).unwrap();
+        assert_eq!(items, [7, 3]);
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        assert_eq!(reader.info().color_type, png::ColorType::Indexed);
+        let mut indices = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut indices).unwrap();
+        for (index, id) in indices.iter().zip(&ids.buffer) {
+            let expected = match id {
+                Some(id) => items.iter().position(|i| i == id).unwrap() + 1,
+                None => 0,
+            };
+            assert_eq!(*index as usize, expected);
+        }
+        let many = Grid {
+            buffer: (0..W * H).map(Some).collect(),
+            ..ids
+        };
+        assert!(write_indexed_png(&many, std::io::sink()).is_err());
+
+        let disk = Grid::from_sdf(W, H, &Disk::new(0.2));
+        let mut tiff = Cursor::new(vec![]);
+        write_float_tiff(&disk, &mut tiff).unwrap();
+        tiff.set_position(0);
+        let mut decoder = tiff::decoder::Decoder::new(tiff).unwrap();
+        assert_eq!(decoder.dimensions().unwrap(), (W as u32, H as u32));
+        match decoder.read_image().unwrap() {
+            tiff::decoder::DecodingResult::F32(distances) => assert_eq!(distances, disk.buffer),
+            _ => panic!("not a float image"),
+        }
+    }
+}
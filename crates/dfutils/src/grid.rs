@@ -1,12 +1,23 @@
 use crate::{
-    gridref::{GridRef, GridRefMut},
-    sdf::Sdf,
+    gridref::{pixel_at, pixel_center, view_bounds, GridRef, GridRefMut},
+    interval::{Aabb, Interval, Prune},
+    sdf::{Sdf, SignedDistance, WithData},
 };
 use glam::{vec2, Vec2};
 
+/// Side in pixels of the tiles that [Grid::rasterize] processes in parallel
+const TILE_SIZE: usize = 64;
+
+/// Side in pixels of the tiles that [Grid::rasterize] evaluates pixel by pixel
+const LEAF_SIZE: usize = 8;
+
+/// Values at the centers of the pixels of a `w` by `h` split of `bounds`, row by row from the
+/// top
+#[derive(Clone)]
 pub struct Grid<T> {
     pub w: usize,
     pub h: usize,
+    pub bounds: Aabb,
     pub buffer: Vec<T>,
 }
 
@@ -14,31 +25,37 @@ impl<#[cfg(feature = "rayon")] T: Send, #[cfg(not(feature = "rayon"))] T> Grid<T
 where
     T: Default + Clone + Copy,
 {
+    /// A grid over the view of a viewport of the same size, see [view_bounds]
     pub fn new(w: usize, h: usize) -> Self {
+        Self::with_bounds(w, h, view_bounds(w, h))
+    }
+
+    pub fn with_bounds(w: usize, h: usize, bounds: Aabb) -> Self {
         Self {
             w,
             h,
+            bounds,
             buffer: vec![Default::default(); w * h],
         }
     }
 
     pub fn as_ref(&self) -> GridRef<'_, T> {
-        GridRef::new(self.w, self.h, &self.buffer)
+        GridRef::with_bounds(self.w, self.h, self.bounds, &self.buffer)
     }
 
     pub fn as_ref_mut(&mut self) -> GridRefMut<'_, T> {
-        GridRefMut::new(self.w, self.h, &mut self.buffer)
+        GridRefMut::with_bounds(self.w, self.h, self.bounds, &mut self.buffer)
     }
 
     pub fn from_sdf<
         #[cfg(feature = "rayon")] S: Sdf<T = T> + Sync,
         #[cfg(not(feature = "rayon"))] S: Sdf<T = T>,
     >(
-        rows: usize,
-        cols: usize,
+        w: usize,
+        h: usize,
         sdf: &S,
     ) -> Self {
-        let mut result = Self::new(rows, cols);
+        let mut result = Self::new(w, h);
         result.update(sdf);
         result
     }
@@ -53,36 +70,33 @@ where
         #[cfg(feature = "rayon")]
         use rayon::prelude::*;
 
-        let ar = self.aspect_ratio();
+        if self.w == 0 {
+            return;
+        }
+        let (w, h, bounds) = (self.w, self.h, self.bounds);
 
+        // a row at a time, for sdfs which evaluate several points at once
         #[cfg(feature = "rayon")]
-        let iter = self.buffer.par_iter_mut();
+        let rows = self.buffer.par_chunks_mut(w);
         #[cfg(not(feature = "rayon"))]
-        let iter = self.buffer.iter_mut();
-
-        iter.enumerate().for_each(|(i, value)| {
-            let y = i / self.w;
-            let x = i - y * self.w;
-            let p = vec2(
-                (x as f32 / self.w as f32 - 0.5) * ar,
-                0.5 - y as f32 / self.h as f32,
-            ) + 0.5 / self.w as f32;
-            debug_assert!(p.x.abs() < 0.5 * ar && p.y.abs() < 0.5);
-            *value = sdf.signed_distance(p);
+        let rows = self.buffer.chunks_mut(w);
+
+        rows.enumerate().for_each(|(y, values)| {
+            let points: Vec<Vec2> = (0..values.len())
+                .map(|x| pixel_center(bounds, w, h, x, y))
+                .collect();
+            sdf.signed_distance_many(&points, values);
         });
     }
 
+    /// Change to `w` by `h` pixels over the view of a viewport of that size, resetting the
+    /// values
     pub fn resize(&mut self, w: usize, h: usize) {
         self.w = w;
         self.h = h;
-        let new_size = w * h;
-        if new_size > self.buffer.len() {
-            self.buffer.resize(new_size, Default::default());
-        }
-    }
-
-    fn aspect_ratio(&self) -> f32 {
-        self.w as f32 / self.h as f32
+        self.bounds = view_bounds(w, h);
+        self.buffer.clear();
+        self.buffer.resize(w * h, Default::default());
     }
 
     pub fn get(&self, x: usize, y: usize) -> T {
@@ -93,7 +107,726 @@ where
         self.buffer[y * self.w + x] = value;
     }
 
+    /// See [pixel_center]
+    pub fn position(&self, x: usize, y: usize) -> Vec2 {
+        pixel_center(self.bounds, self.w, self.h, x, y)
+    }
+
+    /// See [pixel_at]
+    pub fn pixel(&self, p: Vec2) -> (usize, usize) {
+        pixel_at(self.bounds, self.w, self.h, p)
+    }
+
+    /// Value of the pixel containing `p`
     pub fn signed_distance(&self, p: Vec2) -> T {
         self.as_ref().signed_distance(p)
     }
 }
+
+impl<T: Copy> Grid<T> {
+    /// Size of a pixel in world units
+    pub fn pixel_size(&self) -> Vec2 {
+        self.bounds.size() / vec2(self.w as f32, self.h as f32)
+    }
+
+    /// `f` of each value, over the same pixels
+    pub fn map<U>(&self, f: impl Fn(T) -> U) -> Grid<U> {
+        Grid {
+            w: self.w,
+            h: self.h,
+            bounds: self.bounds,
+            buffer: self.buffer.iter().map(|v| f(*v)).collect(),
+        }
+    }
+
+    /// `f` of the values of each pixel in `self` and `other`, which must have the same size
+    pub fn zip<U: Copy, V>(&self, other: &Grid<U>, f: impl Fn(T, U) -> V) -> Grid<V> {
+        assert_eq!(
+            (self.w, self.h),
+            (other.w, other.h),
+            "grids of different sizes"
+        );
+        Grid {
+            w: self.w,
+            h: self.h,
+            bounds: self.bounds,
+            buffer: self
+                .buffer
+                .iter()
+                .zip(&other.buffer)
+                .map(|(a, b)| f(*a, *b))
+                .collect(),
+        }
+    }
+
+    /// The values of each pixel in `grids` combined by `f`, like the union of several fields
+    /// with `|a, b| a.union(&b)`. The grids must have the same size, and there must be at least
+    /// one.
+    pub fn reduce(grids: &[&Grid<T>], f: impl Fn(T, T) -> T) -> Grid<T> {
+        let (first, rest) = grids.split_first().expect("no grids to reduce");
+        rest.iter()
+            .fold((*first).clone(), |result, grid| result.zip(grid, &f))
+    }
+}
+
+/// Range and average of the finite distances of a grid
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stats {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+}
+
+impl<T: WithData> Grid<T> {
+    /// The data of each pixel, like the ids of the closest items
+    pub fn data(&self) -> Grid<T::Data> {
+        self.map(|v| v.data())
+    }
+
+    /// The data of the pixels inside the contour, as masks of the shapes
+    pub fn data_inside(&self) -> Grid<Option<T::Data>> {
+        self.map(|v| (v.value() < 0.0).then(|| v.data()))
+    }
+}
+
+impl<T: SignedDistance> Grid<T> {
+    pub fn distances(&self) -> Grid<f32> {
+        self.map(|v| v.value())
+    }
+
+    /// None if no distance is finite
+    pub fn stats(&self) -> Option<Stats> {
+        let mut count = 0;
+        let mut sum = 0.0;
+        let mut min = f32::INFINITY;
+        let mut max = f32::NEG_INFINITY;
+        for d in self.buffer.iter().map(|v| v.value()) {
+            if d.is_finite() {
+                count += 1;
+                sum += d as f64;
+                min = min.min(d);
+                max = max.max(d);
+            }
+        }
+        (count > 0).then(|| Stats {
+            min,
+            max,
+            mean: (sum / count as f64) as f32,
+        })
+    }
+
+    /// Replace the distances by the Euclidean distances to the zero set, keeping the signs
+    ///
+    /// Operators other than union only give bounds of the distance away from the contour. The
+    /// contour is sampled where it crosses the segments between the centers of neighboring
+    /// pixels, located by linear interpolation, and the distances to the nearest of these points
+    /// are computed exactly by the separable Euclidean distance transform of Felzenszwalb and
+    /// Huttenlocher. The crossings of vertical segments lie on the columns of pixels, so their
+    /// distances are found along the columns and then along the rows, and the other way around
+    /// for the crossings of horizontal segments.
+    pub fn exactify(&mut self) {
+        let (w, h) = (self.w, self.h);
+        let pixel = self.pixel_size();
+        let crossing = |a: usize, b: usize| {
+            let (va, vb) = (self.buffer[a].value(), self.buffer[b].value());
+            ((va < 0.0) != (vb < 0.0)).then(|| va / (va - vb))
+        };
+
+        // squared distances to the crossings on the same column, and on the same row
+        let mut columns = vec![f32::INFINITY; w * h];
+        for x in 0..w {
+            let crossings: Vec<f32> = (1..h)
+                .filter_map(|y| {
+                    Some(((y - 1) as f32 + crossing((y - 1) * w + x, y * w + x)?) * pixel.y)
+                })
+                .collect();
+            for (y, d) in nearest_crossings(&crossings, h, pixel.y)
+                .into_iter()
+                .enumerate()
+            {
+                columns[y * w + x] = d;
+            }
+        }
+        let mut rows = vec![f32::INFINITY; w * h];
+        for y in 0..h {
+            let crossings: Vec<f32> = (1..w)
+                .filter_map(|x| {
+                    Some(((x - 1) as f32 + crossing(y * w + x - 1, y * w + x)?) * pixel.x)
+                })
+                .collect();
+            rows[y * w..(y + 1) * w].copy_from_slice(&nearest_crossings(&crossings, w, pixel.x));
+        }
+
+        let mut squared = vec![f32::INFINITY; w * h];
+        for y in 0..h {
+            squared[y * w..(y + 1) * w]
+                .copy_from_slice(&lower_envelope(&columns[y * w..(y + 1) * w], pixel.x));
+        }
+        for x in 0..w {
+            let column: Vec<f32> = (0..h).map(|y| rows[y * w + x]).collect();
+            for (y, d) in lower_envelope(&column, pixel.y).into_iter().enumerate() {
+                squared[y * w + x] = squared[y * w + x].min(d);
+            }
+        }
+
+        for (v, d) in self.buffer.iter_mut().zip(squared) {
+            if d.is_finite() {
+                let d = d.sqrt();
+                *v = v.with_new_distance(if v.value() < 0.0 { -d } else { d });
+            }
+        }
+    }
+
+    /// Position of pixel `(x, y)` from the first one, in world units with y down
+    pub(crate) fn pixel_offset(&self) -> impl Fn(usize, usize) -> Vec2 {
+        let pixel = self.pixel_size();
+        move |x, y| vec2(x as f32, y as f32) * pixel
+    }
+
+    /// Closest contour point of the pixels where the sign changes, located from the gradient,
+    /// as given by [Grid::pixel_offset]
+    pub(crate) fn contour_points(&self) -> Vec<Option<Vec2>> {
+        let (w, h) = (self.w, self.h);
+        let pixel = self.pixel_size();
+        let position = self.pixel_offset();
+        let value = |x: usize, y: usize| self.buffer[y * w + x].value();
+        let inside = |x: usize, y: usize| value(x, y) < 0.0;
+
+        let mut closest = vec![None; w * h];
+        for y in 0..h {
+            for x in 0..w {
+                let crosses = (x > 0 && inside(x - 1, y) != inside(x, y))
+                    || (x + 1 < w && inside(x + 1, y) != inside(x, y))
+                    || (y > 0 && inside(x, y - 1) != inside(x, y))
+                    || (y + 1 < h && inside(x, y + 1) != inside(x, y));
+                if !crosses {
+                    continue;
+                }
+                let (x0, x1) = (x.saturating_sub(1), (x + 1).min(w - 1));
+                let (y0, y1) = (y.saturating_sub(1), (y + 1).min(h - 1));
+                let gradient = vec2(value(x1, y) - value(x0, y), value(x, y1) - value(x, y0))
+                    / (vec2((x1 - x0) as f32, (y1 - y0) as f32) * pixel);
+                // first order step to the zero, which also holds for fields scaled away from
+                // the contour
+                let step = if gradient.length_squared() > 0.0 {
+                    value(x, y) * gradient / gradient.length_squared()
+                } else {
+                    Vec2::ZERO
+                };
+                closest[y * w + x] = Some(position(x, y) - step);
+            }
+        }
+        closest
+    }
+
+    /// Set the distances to the distances to the closest contour points, keeping the signs
+    pub(crate) fn set_closest(&mut self, closest: &[Option<Vec2>]) {
+        let (w, h) = (self.w, self.h);
+        let position = self.pixel_offset();
+        for y in 0..h {
+            for x in 0..w {
+                if let Some(c) = closest[y * w + x] {
+                    let d = position(x, y).distance(c);
+                    let old = &mut self.buffer[y * w + x];
+                    let d = if old.value() < 0.0 { -d } else { d };
+                    *old = old.with_new_distance(d);
+                }
+            }
+        }
+    }
+}
+
+/// Squared distances from the `n` pixels of a line, `spacing` apart, to the nearest of the sorted
+/// `crossings`, positions along the line from the first pixel
+fn nearest_crossings(crossings: &[f32], n: usize, spacing: f32) -> Vec<f32> {
+    let mut next = 0;
+    (0..n)
+        .map(|i| {
+            let p = i as f32 * spacing;
+            while next + 1 < crossings.len() && crossings[next + 1] <= p {
+                next += 1;
+            }
+            // the nearest crossings are the last one before `p` and the first one after it
+            crossings[next..(next + 2).min(crossings.len())]
+                .iter()
+                .map(|c| (p - c) * (p - c))
+                .fold(f32::INFINITY, f32::min)
+        })
+        .collect()
+}
+
+/// The least `((i - j) spacing)² + f[j]` over `j` for each `i`, from the lower envelope of these
+/// parabolas in one pass, after Felzenszwalb and Huttenlocher
+fn lower_envelope(f: &[f32], spacing: f32) -> Vec<f32> {
+    let position = |i: usize| i as f32 * spacing;
+    // where the parabolas of `a` and `b > a` cross
+    let intersection = |a: usize, b: usize| {
+        let (za, zb) = (position(a), position(b));
+        ((f[b] + zb * zb) - (f[a] + za * za)) / (2.0 * (zb - za))
+    };
+    // parabolas of the envelope, with where each one starts being the lowest
+    let mut vertices: Vec<usize> = Vec::new();
+    let mut starts: Vec<f32> = Vec::new();
+    for (j, v) in f.iter().enumerate() {
+        if !v.is_finite() {
+            continue;
+        }
+        while let (Some(last), Some(start)) = (vertices.last(), starts.last()) {
+            if intersection(*last, j) <= *start {
+                vertices.pop();
+                starts.pop();
+            } else {
+                break;
+            }
+        }
+        starts.push(
+            vertices
+                .last()
+                .map_or(f32::NEG_INFINITY, |last| intersection(*last, j)),
+        );
+        vertices.push(j);
+    }
+    if vertices.is_empty() {
+        return vec![f32::INFINITY; f.len()];
+    }
+    let mut k = 0;
+    (0..f.len())
+        .map(|i| {
+            let z = position(i);
+            while k + 1 < vertices.len() && starts[k + 1] < z {
+                k += 1;
+            }
+            let d = z - position(vertices[k]);
+            d * d + f[vertices[k]]
+        })
+        .collect()
+}
+
+impl<
+        #[cfg(feature = "rayon")] T: SignedDistance + Send,
+        #[cfg(not(feature = "rayon"))] T: SignedDistance,
+    > Grid<T>
+{
+    /// Like [Grid::update], for fields only needed within `band` of the contour
+    ///
+    /// The grid is split as a quadtree. Tiles whose distances are all beyond `band` are filled
+    /// with lower bounds of their distances, and the others are evaluated with `sdf` pruned to
+    /// what matters inside them.
+    pub fn rasterize<
+        #[cfg(feature = "rayon")] S: Prune<T = T> + Sync,
+        #[cfg(not(feature = "rayon"))] S: Prune<T = T>,
+    >(
+        &mut self,
+        sdf: &S,
+        band: f32,
+    ) {
+        #[cfg(feature = "rayon")]
+        use rayon::prelude::*;
+
+        if self.w == 0 {
+            return;
+        }
+        let (w, h, bounds) = (self.w, self.h, self.bounds);
+
+        #[cfg(feature = "rayon")]
+        let strips = self.buffer.par_chunks_mut(w * TILE_SIZE);
+        #[cfg(not(feature = "rayon"))]
+        let strips = self.buffer.chunks_mut(w * TILE_SIZE);
+
+        strips.enumerate().for_each(|(i, strip)| {
+            let y0 = i * TILE_SIZE;
+            let mut strip = Strip {
+                w,
+                h,
+                bounds,
+                y0,
+                values: strip,
+            };
+            for x0 in (0..w).step_by(TILE_SIZE) {
+                let tile = Tile {
+                    x0,
+                    y0,
+                    x1: (x0 + TILE_SIZE).min(w),
+                    y1: (y0 + TILE_SIZE).min(h),
+                };
+                let (pruned, interval) = sdf.prune(tile.aabb(bounds, w, h));
+                strip.rasterize(tile, &pruned, interval, band);
+            }
+        });
+    }
+}
+
+/// Pixels `x0..x1` of rows `y0..y1`
+#[derive(Clone, Copy)]
+pub(crate) struct Tile {
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize,
+}
+
+impl Tile {
+    /// Bounds of the centers of the pixels, in a grid of `w` by `h` pixels over `bounds`
+    pub fn aabb(&self, bounds: Aabb, w: usize, h: usize) -> Aabb {
+        Aabb::from_points(&[
+            pixel_center(bounds, w, h, self.x0, self.y0),
+            pixel_center(bounds, w, h, self.x1 - 1, self.y1 - 1),
+        ])
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.x1 - self.x0 <= LEAF_SIZE && self.y1 - self.y0 <= LEAF_SIZE
+    }
+
+    fn quadrants(&self) -> [Tile; 4] {
+        let x = (self.x0 + self.x1).div_ceil(2);
+        let y = (self.y0 + self.y1).div_ceil(2);
+        [
+            Tile {
+                x1: x,
+                y1: y,
+                ..*self
+            },
+            Tile {
+                x0: x,
+                y1: y,
+                ..*self
+            },
+            Tile {
+                x1: x,
+                y0: y,
+                ..*self
+            },
+            Tile {
+                x0: x,
+                y0: y,
+                ..*self
+            },
+        ]
+    }
+
+    fn is_empty(&self) -> bool {
+        self.x0 == self.x1 || self.y0 == self.y1
+    }
+}
+
+/// Rows of a grid of `w` by `h` pixels over `bounds` starting at row `y0`
+struct Strip<'a, T> {
+    w: usize,
+    h: usize,
+    bounds: Aabb,
+    y0: usize,
+    values: &'a mut [T],
+}
+
+impl<T: SignedDistance> Strip<'_, T> {
+    fn rasterize<S: Prune<T = T, Pruned = S>>(
+        &mut self,
+        tile: Tile,
+        sdf: &S,
+        interval: Interval,
+        band: f32,
+    ) {
+        let (w, h, bounds) = (self.w, self.h, self.bounds);
+        if interval.is_beyond(band) {
+            // the data at the center, with the distance of the pixels closest to the contour
+            let d = sdf.signed_distance(tile.aabb(bounds, w, h).center());
+            let bound = d.with_bound(if interval.min > band {
+                interval.min
+            } else {
+                interval.max
+            });
+            for y in tile.y0..tile.y1 {
+                let row = (y - self.y0) * w;
+                self.values[row + tile.x0..row + tile.x1].fill(bound);
+            }
+        } else if tile.is_leaf() {
+            let mut points = [Vec2::ZERO; LEAF_SIZE];
+            let n = tile.x1 - tile.x0;
+            for y in tile.y0..tile.y1 {
+                for (i, p) in points[..n].iter_mut().enumerate() {
+                    *p = pixel_center(bounds, w, h, tile.x0 + i, y);
+                }
+                let row = (y - self.y0) * w;
+                sdf.signed_distance_many(
+                    &points[..n],
+                    &mut self.values[row + tile.x0..row + tile.x1],
+                );
+            }
+        } else {
+            for quadrant in tile.quadrants() {
+                if !quadrant.is_empty() {
+                    let (pruned, interval) = sdf.prune(quadrant.aabb(bounds, w, h));
+                    self.rasterize(quadrant, &pruned, interval, band);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gridref,
+        interval::Aabb,
+        primitives::*,
+        primitives_enum,
+        sdf::{Track, Tracked},
+    };
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn grid() {
+        const ROWS: usize = 32;
+        const COLS: usize = 32;
+        const E: f32 = 1.0 / (COLS as f32) + f32::EPSILON;
+
+        let disk = Disk::new(0.1);
+        let mut grid = Grid::from_sdf(ROWS, COLS, &disk);
+        let d = grid.signed_distance(vec2(0.0, 0.4));
+        assert_approx_eq!(d, 0.3, E);
+        let d = grid.as_ref().signed_distance(vec2(0.0, 0.4));
+        assert_approx_eq!(d, 0.3, E);
+
+        let torus = Torus::new(0.2, 0.1);
+        grid.update(&torus);
+        let d = grid.signed_distance(vec2(0.4, 0.0));
+        assert_approx_eq!(d, 0.1, E);
+        let d = grid.as_ref().signed_distance(vec2(0.4, 0.0));
+        assert_approx_eq!(d, 0.1, E);
+    }
+
+    #[test]
+    fn pixels() {
+        use gridref::{pixel_at, pixel_center};
+        const W: usize = 48;
+        const H: usize = 30;
+
+        // pixel centers match the mapping of the runner, `shared::from_pixels`
+        let mut grid = Grid::<f32>::new(W, H);
+        for (x, y) in [(0, 0), (W - 1, 0), (7, 11), (W - 1, H - 1)] {
+            let pixel = vec2(x as f32 + 0.5, y as f32 + 0.5);
+            let expected = (vec2(pixel.x, -pixel.y) - 0.5 * vec2(W as f32, -(H as f32))) / H as f32;
+            assert!(grid.position(x, y).abs_diff_eq(expected, 1e-6));
+            assert_eq!(grid.pixel(expected), (x, y));
+        }
+        // points beyond the bounds belong to the pixels on the edges
+        assert_eq!(grid.pixel(vec2(-10.0, 10.0)), (0, 0));
+        assert_eq!(grid.pixel(vec2(10.0, -10.0)), (W - 1, H - 1));
+
+        let bounds = Aabb::new(vec2(-2.0, 1.0), vec2(3.0, 1.5));
+        for y in 0..H {
+            for x in 0..W {
+                assert_eq!(
+                    pixel_at(bounds, W, H, pixel_center(bounds, W, H, x, y)),
+                    (x, y)
+                );
+            }
+        }
+
+        // symmetric shapes give symmetric grids, whatever the aspect ratio
+        grid.update(&Disk::new(0.2));
+        for y in 0..H {
+            for x in 0..W {
+                assert_approx_eq!(grid.get(x, y), grid.get(W - 1 - x, y));
+                assert_approx_eq!(grid.get(x, y), grid.get(x, H - 1 - y));
+            }
+        }
+
+        grid.resize(H, W);
+        assert_eq!(grid.buffer.len(), W * H);
+        grid.resize(W / 2, H / 2);
+        assert_eq!(grid.buffer.len(), W / 2 * H / 2);
+        assert_eq!(grid.bounds, gridref::view_bounds(W / 2, H / 2));
+    }
+
+    #[test]
+    fn combinators() {
+        const W: usize = 40;
+        const H: usize = 30;
+
+        let disk = Grid::from_sdf(W, H, &Disk::new(0.2));
+        let torus = Grid::from_sdf(W, H, &Torus::new(0.3, 0.05));
+        let union = Grid::reduce(&[&disk, &torus], |a, b| a.union(&b));
+        for (i, d) in union.buffer.iter().enumerate() {
+            assert_eq!(*d, disk.buffer[i].min(torus.buffer[i]));
+        }
+        let offset = disk.zip(&torus, |a, b| a - b).map(|d| d + 1.0);
+        assert_approx_eq!(offset.get(3, 4), disk.get(3, 4) - torus.get(3, 4) + 1.0);
+
+        let stats = disk.stats().unwrap();
+        assert_eq!(
+            stats.min,
+            disk.buffer.iter().copied().fold(f32::INFINITY, f32::min)
+        );
+        assert!(stats.min < 0.0 && stats.mean > stats.min && stats.mean < stats.max);
+        assert_eq!(Grid::<f32>::new(0, 0).stats(), None);
+
+        let tracked = disk.map(Tracked::exact);
+        assert_eq!(tracked.distances().buffer, disk.buffer);
+    }
+
+    #[test]
+    fn rasterize() {
+        const SIZE: usize = 200;
+        const BAND: f32 = 0.05;
+
+        let torus = primitives_enum::Shape::Torus(Torus::new(0.3, 0.02));
+        let reference = Grid::from_sdf(SIZE, SIZE, &torus);
+        let mut grid = Grid::new(SIZE, SIZE);
+        grid.rasterize(&torus, BAND);
+        for (d, expected) in grid.buffer.iter().zip(&reference.buffer) {
+            if expected.abs() <= BAND {
+                assert_eq!(d, expected);
+            } else {
+                assert_eq!(d.signum(), expected.signum());
+                assert!(d.abs() <= expected.abs() + f32::EPSILON);
+            }
+        }
+    }
+
+    #[test]
+    fn exactness() {
+        const SIZE: usize = 64;
+        const E: f32 = 1.0 / SIZE as f32;
+
+        // intersection of two disks, only a bound outside, most of all beyond its tips
+        struct Lens;
+        impl Sdf for Lens {
+            type T = Tracked<f32>;
+            fn signed_distance(&self, p: Vec2) -> Tracked<f32> {
+                let disk = Track(Disk::new(0.3));
+                let a = disk.signed_distance(p - vec2(0.15, 0.0));
+                let b = disk.signed_distance(p + vec2(0.15, 0.0));
+                a.intersect(&b)
+            }
+        }
+        let tip = vec2(0.0, 0.4);
+        let d = Lens.signed_distance(tip);
+        assert!(!d.exact);
+        assert!(d.value() < 0.4 - (0.3f32.powi(2) - 0.15f32.powi(2)).sqrt() - 0.01);
+        assert!(Lens.signed_distance(Vec2::ZERO).exact);
+
+        let mut grid = Grid::from_sdf(SIZE, SIZE, &Lens);
+        let bounds = grid.buffer.clone();
+        grid.exactify();
+
+        // the distances are those to the nearest crossing of the contour between pixels
+        let position = grid.pixel_offset();
+        let mut crossings = vec![];
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let a = bounds[y * SIZE + x].value();
+                for (nx, ny) in [(x + 1, y), (x, y + 1)] {
+                    if nx < SIZE && ny < SIZE {
+                        let b = bounds[ny * SIZE + nx].value();
+                        if (a < 0.0) != (b < 0.0) {
+                            let t = a / (a - b);
+                            crossings.push(position(x, y).lerp(position(nx, ny), t));
+                        }
+                    }
+                }
+            }
+        }
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let nearest = crossings
+                    .iter()
+                    .map(|c| c.distance(position(x, y)))
+                    .fold(f32::INFINITY, f32::min);
+                assert_approx_eq!(grid.get(x, y).value().abs(), nearest, 1e-5);
+            }
+        }
+
+        let mut largest_error = 0.0f32;
+        for (bound, exact) in bounds.iter().zip(&grid.buffer) {
+            assert_eq!(bound.value() < 0.0, exact.value() < 0.0);
+            assert!(bound.value().abs() <= exact.value().abs() + E);
+            if bound.exact {
+                assert_approx_eq!(bound.value(), exact.value(), E);
+            }
+            largest_error = largest_error.max(exact.value() - bound.value());
+        }
+        assert!(largest_error > 0.01);
+
+        // exactifying exact distances changes nothing but rounding
+        let disk = Disk::new(0.2);
+        let mut grid = Grid::from_sdf(SIZE, SIZE, &disk);
+        let exact = grid.buffer.clone();
+        grid.exactify();
+        for (a, b) in exact.iter().zip(&grid.buffer) {
+            assert_approx_eq!(a, b, E);
+        }
+    }
+
+    mod golden {
+        use super::*;
+        use crate::testing::FnSdf;
+        use std::path::PathBuf;
+
+        const SIZE: usize = 128;
+
+        /// Compare the coverage of `sdf` rasterized by [Grid] with `golden/{name}.png`, or
+        /// overwrite the image when `UPDATE_GOLDEN` is set
+        fn check<S: Sdf<T = f32> + Sync>(name: &str, sdf: &S) {
+            let grid = Grid::from_sdf(SIZE, SIZE, sdf);
+            let pixels: Vec<u8> = grid
+                .buffer
+                .iter()
+                .map(|d| (255.0 * (0.5 - d * SIZE as f32).clamp(0.0, 1.0)).round() as u8)
+                .collect();
+            let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("golden")
+                .join(format!("{name}.png"));
+
+            if std::env::var_os("UPDATE_GOLDEN").is_some() {
+                let file = std::io::BufWriter::new(std::fs::File::create(&path).unwrap());
+                let mut encoder = png::Encoder::new(file, SIZE as u32, SIZE as u32);
+                encoder.set_color(png::ColorType::Grayscale);
+                encoder.set_depth(png::BitDepth::Eight);
+                let mut writer = encoder.write_header().unwrap();
+                writer.write_image_data(&pixels).unwrap();
+                return;
+            }
+
+            let decoder = png::Decoder::new(std::fs::File::open(&path).unwrap());
+            let mut reader = decoder.read_info().unwrap();
+            let mut golden = vec![0; reader.output_buffer_size()];
+            reader.next_frame(&mut golden).unwrap();
+            let differing = pixels
+                .iter()
+                .zip(&golden)
+                .filter(|(a, b)| a.abs_diff(**b) > 1)
+                .count();
+            assert!(
+                differing == 0,
+                "{differing} pixels differ from {}, run with UPDATE_GOLDEN=1 if intended",
+                path.display()
+            );
+        }
+
+        #[test]
+        fn primitives() {
+            check("disk", &Disk::new(0.3));
+            check("torus", &Torus::new(0.3, 0.05));
+            check("rectangle", &Rectangle::new(0.6, 0.3));
+            check("cross", &Cross::new(0.4, 0.1));
+            check("plane", &Plane::new(vec2(0.6, 0.8)));
+            check(
+                "line_segment",
+                &LineSegment::new(vec2(-0.3, -0.2), vec2(0.3, 0.2)),
+            );
+        }
+
+        #[test]
+        fn operators() {
+            let disk = |p: Vec2| Disk::new(0.25).signed_distance(p - vec2(0.1, 0.0));
+            let rectangle = |p: Vec2| Rectangle::new(0.5, 0.3).signed_distance(p + vec2(0.1, 0.0));
+            check("union", &FnSdf(|p| disk(p).union(&rectangle(p))));
+            check("intersection", &FnSdf(|p| disk(p).intersect(&rectangle(p))));
+            check("difference", &FnSdf(|p| disk(p).subtract(&rectangle(p))));
+            check("xor", &FnSdf(|p| disk(p).xor(&rectangle(p))));
+        }
+    }
+}
@@ -1,32 +1,80 @@
-use glam::Vec2;
-#[cfg(not(feature = "std"))]
-use num_traits::Float;
+use crate::interval::Aabb;
+use glam::{vec2, Vec2};
 
+/// Area of the view of the runner for a viewport of `w` by `h` pixels, as mapped by
+/// `shared::from_pixels`: y from -0.5 at the bottom to 0.5 at the top, and x over the same scale
+pub fn view_bounds(w: usize, h: usize) -> Aabb {
+    let half_size = vec2(0.5 * w as f32 / h as f32, 0.5);
+    Aabb::new(-half_size, half_size)
+}
+
+/// Center of pixel `(x, y)` of a `w` by `h` split of `bounds`, counting rows from the top
+pub fn pixel_center(bounds: Aabb, w: usize, h: usize, x: usize, y: usize) -> Vec2 {
+    let pixel_size = bounds.size() / vec2(w as f32, h as f32);
+    vec2(
+        bounds.min.x + (x as f32 + 0.5) * pixel_size.x,
+        bounds.max.y - (y as f32 + 0.5) * pixel_size.y,
+    )
+}
+
+/// Pixel of a `w` by `h` split of `bounds` containing `p`, the pixels on the edges extending
+/// beyond the bounds
+pub fn pixel_at(bounds: Aabb, w: usize, h: usize, p: Vec2) -> (usize, usize) {
+    let q =
+        (vec2(p.x - bounds.min.x, bounds.max.y - p.y) / bounds.size()) * vec2(w as f32, h as f32);
+    let q = q.max(Vec2::ZERO);
+    ((q.x as usize).min(w - 1), (q.y as usize).min(h - 1))
+}
+
+/// Values of the pixels of a `w` by `h` split of `bounds`, row by row from the top
 #[derive(Clone, Copy)]
 pub struct GridRef<'a, T> {
     w: usize,
     h: usize,
+    bounds: Aabb,
     buffer: &'a [T],
 }
 
 impl<'a, T: Copy> GridRef<'a, T> {
+    /// A grid over the view of a viewport of the same size, see [view_bounds]
     pub fn new(w: usize, h: usize, buffer: &'a [T]) -> Self {
-        Self { w, h, buffer }
+        Self::with_bounds(w, h, view_bounds(w, h), buffer)
+    }
+
+    pub fn with_bounds(w: usize, h: usize, bounds: Aabb, buffer: &'a [T]) -> Self {
+        Self {
+            w,
+            h,
+            bounds,
+            buffer,
+        }
     }
 
     pub fn aspect_ratio(&self) -> f32 {
         self.w as f32 / self.h as f32
     }
 
+    pub fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+
     pub fn get(&self, x: usize, y: usize) -> T {
         self.buffer[y * self.w + x]
     }
 
+    /// See [pixel_center]
+    pub fn position(&self, x: usize, y: usize) -> Vec2 {
+        pixel_center(self.bounds, self.w, self.h, x, y)
+    }
+
+    /// See [pixel_at]
+    pub fn pixel(&self, p: Vec2) -> (usize, usize) {
+        pixel_at(self.bounds, self.w, self.h, p)
+    }
+
+    /// Value of the pixel containing `p`
     pub fn signed_distance(&self, p: Vec2) -> T {
-        let ar = self.aspect_ratio();
-        debug_assert!(p.x.abs() < 0.5 * ar && p.y.abs() < 0.5);
-        let x = ((p.x + 0.5 * ar) / ar * self.w as f32) as usize;
-        let y = ((0.5 - p.y) * self.h as f32) as usize;
+        let (x, y) = self.pixel(p);
         self.get(x, y)
     }
 }
@@ -34,16 +82,26 @@ impl<'a, T: Copy> GridRef<'a, T> {
 pub struct GridRefMut<'a, T> {
     w: usize,
     h: usize,
+    bounds: Aabb,
     buffer: &'a mut [T],
 }
 
 impl<'a, T: Copy> GridRefMut<'a, T> {
     pub fn new(w: usize, h: usize, buffer: &'a mut [T]) -> Self {
-        Self { w, h, buffer }
+        Self::with_bounds(w, h, view_bounds(w, h), buffer)
+    }
+
+    pub fn with_bounds(w: usize, h: usize, bounds: Aabb, buffer: &'a mut [T]) -> Self {
+        Self {
+            w,
+            h,
+            bounds,
+            buffer,
+        }
     }
 
     pub fn as_ref(&self) -> GridRef<'_, T> {
-        GridRef::new(self.w, self.h, self.buffer)
+        GridRef::with_bounds(self.w, self.h, self.bounds, self.buffer)
     }
 
     pub fn aspect_ratio(&self) -> f32 {
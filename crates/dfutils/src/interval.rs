@@ -0,0 +1,172 @@
+//! Ranges of distances over boxes, to find the parts of the plane far from the contour of an sdf
+//! and the parts of a composition which matter near it, without evaluating it everywhere
+
+use crate::sdf::Sdf;
+use glam::{vec2, Vec2};
+
+/// Axis-aligned box
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Aabb {
+    pub const fn new(min: Vec2, max: Vec2) -> Self {
+        Self { min, max }
+    }
+
+    /// The smallest box containing all of `points`
+    pub fn from_points(points: &[Vec2]) -> Self {
+        let mut result = Self::new(Vec2::INFINITY, Vec2::NEG_INFINITY);
+        for p in points {
+            result.min = result.min.min(*p);
+            result.max = result.max.max(*p);
+        }
+        result
+    }
+
+    pub fn center(&self) -> Vec2 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    pub fn corners(&self) -> [Vec2; 4] {
+        [
+            self.min,
+            vec2(self.max.x, self.min.y),
+            self.max,
+            vec2(self.min.x, self.max.y),
+        ]
+    }
+
+    /// Range of the distances from the origin to the points of the box
+    pub fn length(&self) -> Interval {
+        let nearest = Vec2::ZERO.clamp(self.min, self.max);
+        let farthest = self.min.abs().max(self.max.abs());
+        Interval::new(nearest.length(), farthest.length())
+    }
+}
+
+/// Range of values, `min` and `max` included
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Interval {
+    pub const fn new(min: f32, max: f32) -> Self {
+        Self { min, max }
+    }
+
+    pub const fn point(value: f32) -> Self {
+        Self::new(value, value)
+    }
+
+    /// The values within `radius` of `value`
+    pub fn around(value: f32, radius: f32) -> Self {
+        Self::new(value - radius, value + radius)
+    }
+
+    /// Grow by `amount` on both sides
+    pub fn widen(self, amount: f32) -> Self {
+        Self::new(self.min - amount, self.max + amount)
+    }
+
+    pub fn abs(self) -> Self {
+        if self.min >= 0.0 {
+            self
+        } else if self.max <= 0.0 {
+            -self
+        } else {
+            Self::new(0.0, self.max.max(-self.min))
+        }
+    }
+
+    /// Whether every value is within `band` of zero, or none is
+    pub fn is_beyond(&self, band: f32) -> bool {
+        self.min > band || self.max < -band
+    }
+
+    /// Range of [SignedDistance::union](crate::sdf::SignedDistance::union)
+    pub fn union(self, other: Self) -> Self {
+        Self::new(self.min.min(other.min), self.max.min(other.max))
+    }
+
+    /// Range of [SignedDistance::intersect](crate::sdf::SignedDistance::intersect)
+    pub fn intersect(self, other: Self) -> Self {
+        Self::new(self.min.max(other.min), self.max.max(other.max))
+    }
+
+    /// Range of [SignedDistance::subtract](crate::sdf::SignedDistance::subtract), which is
+    /// `other` minus `self`
+    pub fn subtract(self, other: Self) -> Self {
+        (-self).intersect(other)
+    }
+
+    pub fn xor(self, other: Self) -> Self {
+        self.intersect(other).subtract(self.union(other))
+    }
+
+    /// Range of [SignedDistance::lerp](crate::sdf::SignedDistance::lerp), for `t` in [0, 1]
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        Self::new(
+            self.min + (other.min - self.min) * t,
+            self.max + (other.max - self.max) * t,
+        )
+    }
+}
+
+impl core::ops::Neg for Interval {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self::new(-self.max, -self.min)
+    }
+}
+
+impl core::ops::Add<f32> for Interval {
+    type Output = Self;
+    fn add(self, value: f32) -> Self {
+        Self::new(self.min + value, self.max + value)
+    }
+}
+
+impl core::ops::Sub<f32> for Interval {
+    type Output = Self;
+    fn sub(self, value: f32) -> Self {
+        Self::new(self.min - value, self.max - value)
+    }
+}
+
+/// Sdfs which can be simplified inside a box, like a composition dropping the operands of a
+/// union which are beaten everywhere in it
+pub trait Prune: Sdf {
+    type Pruned: Prune<T = Self::T, Pruned = Self::Pruned>;
+
+    /// An sdf equal to `self` inside `aabb`, along with the range of its distances there
+    fn prune(&self, aabb: Aabb) -> (Self::Pruned, Interval);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn interval(shape in shape(), a in point(), b in point(), t in (0.0f32..1.0, 0.0f32..1.0)) {
+            let aabb = Aabb::from_points(&[a, b]);
+            let p = aabb.min + aabb.size() * vec2(t.0, t.1);
+            let range = shape.interval(aabb);
+            let d = shape.signed_distance(p);
+            prop_assert!(range.min - EPSILON <= d && d <= range.max + EPSILON, "{} not in {:?}", d, range);
+        }
+    }
+}
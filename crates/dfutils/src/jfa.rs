@@ -0,0 +1,89 @@
+//! Jump flooding, propagating the nearest seeds to all the pixels of a grid in a few passes, each
+//! of which treats the pixels independently, like a dispatch of a compute shader on the GPU
+//!
+//! Each pixel keeps two seeds, in pixels from the first one, in the xy and zw of a [Vec4]: the
+//! nearest pixel inside a mask and the nearest one outside, or when redistancing grids the
+//! nearest contour point in the xy alone. Each pass looks for nearer ones among the seeds of
+//! the pixels a step away in the eight directions, with steps halving from half the size of the
+//! grid down to one pixel.
+
+use glam::{ivec2, UVec2, Vec2, Vec4, Vec4Swizzles};
+
+/// Coordinates of missing seeds, like those of a side of the mask that has no pixels, far from
+/// any contour point
+pub const NONE: f32 = f32::MIN;
+
+fn index(size: UVec2, p: UVec2) -> usize {
+    (p.y * size.x + p.x) as usize
+}
+
+/// Distance from `p` to `seed`, with pixels of size `pixel`
+fn seed_distance(p: Vec2, seed: Vec2, pixel: Vec2) -> f32 {
+    if seed.x == NONE {
+        f32::INFINITY
+    } else {
+        ((seed - p) * pixel).length()
+    }
+}
+
+/// Seeds of the pixel at `p` before the first pass, which is the nearest pixel on its side
+pub fn init(p: UVec2, inside: bool) -> Vec4 {
+    let p = p.as_vec2();
+    if inside {
+        Vec4::new(p.x, p.y, NONE, NONE)
+    } else {
+        Vec4::new(NONE, NONE, p.x, p.y)
+    }
+}
+
+/// Seeds of the pixel at `p` after a pass of `step` pixels, from the `seeds` of a grid of
+/// `size` pixels of size `pixel`, which is [Vec2::ONE] unless they are not square
+pub fn step(seeds: &[Vec4], size: UVec2, p: UVec2, step: u32, pixel: Vec2) -> Vec4 {
+    let position = p.as_vec2();
+    let mut result = seeds[index(size, p)];
+    let mut inside = seed_distance(position, result.xy(), pixel);
+    let mut outside = seed_distance(position, result.zw(), pixel);
+    for dy in -1..2 {
+        for dx in -1..2 {
+            let q = p.as_ivec2() + ivec2(dx, dy) * step as i32;
+            if q.x < 0 || q.y < 0 || q.x >= size.x as i32 || q.y >= size.y as i32 {
+                continue;
+            }
+            let candidate = seeds[index(size, q.as_uvec2())];
+            let d = seed_distance(position, candidate.xy(), pixel);
+            if d < inside {
+                inside = d;
+                result.x = candidate.x;
+                result.y = candidate.y;
+            }
+            let d = seed_distance(position, candidate.zw(), pixel);
+            if d < outside {
+                outside = d;
+                result.z = candidate.z;
+                result.w = candidate.w;
+            }
+        }
+    }
+    result
+}
+
+/// Signed distance in pixels from the pixel at `p` to the edges between the pixels inside and
+/// outside of the mask, negative inside, from its seeds after the last pass
+pub fn distance(seeds: Vec4, p: UVec2, inside: bool) -> f32 {
+    let p = p.as_vec2();
+    if inside {
+        0.5 - seed_distance(p, seeds.zw(), Vec2::ONE)
+    } else {
+        seed_distance(p, seeds.xy(), Vec2::ONE) - 0.5
+    }
+}
+
+/// Steps of the passes over a grid of `size` pixels, the last pass of one pixel being repeated
+/// to fix most of the errors of jump flooding
+#[cfg(not(target_arch = "spirv"))]
+pub fn steps(size: UVec2) -> impl Iterator<Item = u32> {
+    let first = size.max_element().next_power_of_two() / 2;
+    core::iter::successors(Some(first), |step| (*step > 1).then_some(step / 2))
+        .filter(|step| *step > 0)
+        .chain(core::iter::once(1))
+}
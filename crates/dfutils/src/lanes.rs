@@ -0,0 +1,110 @@
+//! Points in groups of four, with one coordinate per [Vec4], to evaluate sdfs on several points
+//! with each operation
+
+use glam::{Vec2, Vec4};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+pub const LANES: usize = 4;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec2x4 {
+    pub x: Vec4,
+    pub y: Vec4,
+}
+
+impl Vec2x4 {
+    pub const fn new(x: Vec4, y: Vec4) -> Self {
+        Self { x, y }
+    }
+
+    pub fn splat(p: Vec2) -> Self {
+        Self::new(Vec4::splat(p.x), Vec4::splat(p.y))
+    }
+
+    /// Up to four points, the missing lanes being zero
+    pub fn load(points: &[Vec2]) -> Self {
+        let mut x = [0.0; LANES];
+        let mut y = [0.0; LANES];
+        for (i, p) in points.iter().take(LANES).enumerate() {
+            x[i] = p.x;
+            y[i] = p.y;
+        }
+        Self::new(Vec4::from_array(x), Vec4::from_array(y))
+    }
+
+    /// Write as many lanes as `out` holds, up to four
+    pub fn store(self, out: &mut [Vec2]) {
+        let x = self.x.to_array();
+        let y = self.y.to_array();
+        for (i, p) in out.iter_mut().take(LANES).enumerate() {
+            *p = Vec2::new(x[i], y[i]);
+        }
+    }
+
+    pub fn dot(self, v: Vec2) -> Vec4 {
+        self.x * v.x + self.y * v.y
+    }
+
+    pub fn length_squared(self) -> Vec4 {
+        self.x * self.x + self.y * self.y
+    }
+
+    pub fn length(self) -> Vec4 {
+        sqrt(self.length_squared())
+    }
+
+    pub fn abs(self) -> Self {
+        Self::new(self.x.abs(), self.y.abs())
+    }
+
+    /// Each point scaled by the matching lane of `s`
+    pub fn scale(self, s: Vec4) -> Self {
+        Self::new(self.x * s, self.y * s)
+    }
+
+    /// Rotation by the angle of the unit vector `r`, as [Vec2::rotate]
+    pub fn rotate(self, r: Vec2) -> Self {
+        Self::new(self.x * r.x - self.y * r.y, self.x * r.y + self.y * r.x)
+    }
+}
+
+impl core::ops::Add<Vec2> for Vec2x4 {
+    type Output = Self;
+    fn add(self, v: Vec2) -> Self {
+        Self::new(self.x + v.x, self.y + v.y)
+    }
+}
+
+impl core::ops::Sub<Vec2> for Vec2x4 {
+    type Output = Self;
+    fn sub(self, v: Vec2) -> Self {
+        Self::new(self.x - v.x, self.y - v.y)
+    }
+}
+
+impl core::ops::Sub for Vec2x4 {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+pub fn sqrt(v: Vec4) -> Vec4 {
+    Vec4::from_array(v.to_array().map(|x| x.sqrt()))
+}
+
+/// Fill `out` with `f` of `points`, evaluated four at a time
+pub fn map(points: &[Vec2], out: &mut [f32], f: impl Fn(Vec2x4) -> Vec4) {
+    for (points, out) in points.chunks(LANES).zip(out.chunks_mut(LANES)) {
+        let d = f(Vec2x4::load(points)).to_array();
+        out.copy_from_slice(&d[..out.len()]);
+    }
+}
+
+/// Fill `out` with `f` of `points`, mapped four at a time
+pub fn map_points(points: &[Vec2], out: &mut [Vec2], f: impl Fn(Vec2x4) -> Vec2x4) {
+    for (points, out) in points.chunks(LANES).zip(out.chunks_mut(LANES)) {
+        f(Vec2x4::load(points)).store(out);
+    }
+}
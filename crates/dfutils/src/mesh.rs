@@ -0,0 +1,253 @@
+//! Triangle meshes of the surface of 3D sdfs, by surface nets, and their export to STL and OBJ
+
+use crate::sdf3::Sdf3;
+use glam::{uvec3, UVec3, Vec3};
+use std::io::{self, Write};
+
+/// Marks grid cells the surface does not cross
+const NO_VERTEX: u32 = u32::MAX;
+
+pub struct Mesh {
+    pub positions: Vec<Vec3>,
+    /// Unit gradients of the sdf at the positions
+    pub normals: Vec<Vec3>,
+    /// Counterclockwise seen from outside
+    pub triangles: Vec<[u32; 3]>,
+}
+
+/// An sdf cut to a box, so that the meshed surface is closed
+struct Clipped<'a, S> {
+    sdf: &'a S,
+    min: Vec3,
+    max: Vec3,
+}
+
+impl<'a, S: Sdf3> Sdf3 for Clipped<'a, S> {
+    fn signed_distance(&self, p: Vec3) -> f32 {
+        let center = 0.5 * (self.min + self.max);
+        let q = (p - center).abs() - 0.5 * (self.max - self.min);
+        let b = q.max(Vec3::ZERO).length() + q.max_element().min(0.0);
+        self.sdf.signed_distance(p).max(b)
+    }
+}
+
+impl Mesh {
+    /// Mesh the part of the surface of `sdf` between `min` and `max`, sampling it every
+    /// `cell_size`. The surface is closed by the sides of the box where it crosses them, so the
+    /// result is always watertight.
+    pub fn from_sdf<
+        #[cfg(feature = "rayon")] S: Sdf3 + Sync,
+        #[cfg(not(feature = "rayon"))] S: Sdf3,
+    >(
+        sdf: &S,
+        min: Vec3,
+        max: Vec3,
+        cell_size: f32,
+    ) -> Self {
+        #[cfg(feature = "rayon")]
+        use rayon::prelude::*;
+
+        let sdf = Clipped { sdf, min, max };
+        // one sample outside the box on each side, so that the border of the grid is outside
+        let origin = min - cell_size;
+        let n = ((max - min) / cell_size).ceil().as_uvec3() + 3;
+        let index = |p: UVec3| (p.x + n.x * (p.y + n.y * p.z)) as usize;
+        let point = |p: UVec3| origin + p.as_vec3() * cell_size;
+
+        let mut values = vec![0.0; (n.x * n.y * n.z) as usize];
+        #[cfg(feature = "rayon")]
+        let iter = values.par_iter_mut();
+        #[cfg(not(feature = "rayon"))]
+        let iter = values.iter_mut();
+        iter.enumerate().for_each(|(i, value)| {
+            let i = i as u32;
+            let p = uvec3(i % n.x, i / n.x % n.y, i / (n.x * n.y));
+            *value = sdf.signed_distance(point(p));
+        });
+        let inside = |p: UVec3| values[index(p)] < 0.0;
+
+        // a vertex in each cell crossed by the surface, at the mean of the crossings of its edges
+        let cells = n - 1;
+        let cell_index = |c: UVec3| (c.x + cells.x * (c.y + cells.y * c.z)) as usize;
+        let mut cell_vertices = vec![NO_VERTEX; (cells.x * cells.y * cells.z) as usize];
+        let mut positions = Vec::new();
+        for z in 0..cells.z {
+            for y in 0..cells.y {
+                for x in 0..cells.x {
+                    let c = uvec3(x, y, z);
+                    let mut sum = Vec3::ZERO;
+                    let mut crossings = 0;
+                    for (a, b) in CUBE_EDGES {
+                        let (a, b) = (c + CUBE_CORNERS[a], c + CUBE_CORNERS[b]);
+                        let (da, db) = (values[index(a)], values[index(b)]);
+                        if (da < 0.0) != (db < 0.0) {
+                            let t = da / (da - db);
+                            sum += point(a).lerp(point(b), t);
+                            crossings += 1;
+                        }
+                    }
+                    if crossings > 0 {
+                        cell_vertices[cell_index(c)] = positions.len() as u32;
+                        positions.push(sum / crossings as f32);
+                    }
+                }
+            }
+        }
+
+        // a quad for each grid edge crossed by the surface, joining the four cells around it
+        let mut triangles = Vec::new();
+        for z in 1..cells.z {
+            for y in 1..cells.y {
+                for x in 1..cells.x {
+                    let p = uvec3(x, y, z);
+                    for axis in 0..3 {
+                        let mut step = UVec3::ZERO;
+                        step[axis] = 1;
+                        if inside(p) == inside(p + step) {
+                            continue;
+                        }
+                        let mut u = UVec3::ZERO;
+                        u[(axis + 1) % 3] = 1;
+                        let mut v = UVec3::ZERO;
+                        v[(axis + 2) % 3] = 1;
+                        // counterclockwise around the axis
+                        let quad =
+                            [p - u - v, p - v, p, p - u].map(|c| cell_vertices[cell_index(c)]);
+                        let [a, b, c, d] = if inside(p) {
+                            quad
+                        } else {
+                            [quad[3], quad[2], quad[1], quad[0]]
+                        };
+                        triangles.push([a, b, c]);
+                        triangles.push([a, c, d]);
+                    }
+                }
+            }
+        }
+
+        #[cfg(feature = "rayon")]
+        let iter = positions.par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let iter = positions.iter();
+        let normals = iter.map(|p| sdf.normal(*p, 0.25 * cell_size)).collect();
+
+        Self {
+            positions,
+            normals,
+            triangles,
+        }
+    }
+
+    /// Normal of a triangle, from the gradients at its vertices
+    fn triangle_normal(&self, triangle: &[u32; 3]) -> Vec3 {
+        triangle
+            .iter()
+            .map(|i| self.normals[*i as usize])
+            .sum::<Vec3>()
+            .normalize_or_zero()
+    }
+
+    /// Write as binary STL
+    pub fn write_stl<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(&[0; 80])?;
+        w.write_all(&(self.triangles.len() as u32).to_le_bytes())?;
+        for triangle in &self.triangles {
+            let normal = self.triangle_normal(triangle);
+            let vertices = triangle.map(|i| self.positions[i as usize]);
+            for v in std::iter::once(normal).chain(vertices) {
+                for x in v.to_array() {
+                    w.write_all(&x.to_le_bytes())?;
+                }
+            }
+            w.write_all(&[0; 2])?;
+        }
+        w.flush()
+    }
+
+    /// Write as Wavefront OBJ, with vertex normals
+    pub fn write_obj<W: Write>(&self, mut w: W) -> io::Result<()> {
+        for p in &self.positions {
+            writeln!(w, "v {} {} {}", p.x, p.y, p.z)?;
+        }
+        for n in &self.normals {
+            writeln!(w, "vn {} {} {}", n.x, n.y, n.z)?;
+        }
+        for [a, b, c] in &self.triangles {
+            let (a, b, c) = (a + 1, b + 1, c + 1);
+            writeln!(w, "f {a}//{a} {b}//{b} {c}//{c}")?;
+        }
+        w.flush()
+    }
+}
+
+const CUBE_CORNERS: [UVec3; 8] = [
+    uvec3(0, 0, 0),
+    uvec3(1, 0, 0),
+    uvec3(0, 1, 0),
+    uvec3(1, 1, 0),
+    uvec3(0, 0, 1),
+    uvec3(1, 0, 1),
+    uvec3(0, 1, 1),
+    uvec3(1, 1, 1),
+];
+
+/// Pairs of [CUBE_CORNERS]
+const CUBE_EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (2, 3),
+    (4, 5),
+    (6, 7),
+    (0, 2),
+    (1, 3),
+    (4, 6),
+    (5, 7),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{primitives::Disk, sdf3::Extrude};
+    use glam::vec3;
+    use std::collections::HashMap;
+
+    #[test]
+    fn mesh() {
+        const CELL: f32 = 0.02;
+
+        let extruded = Extrude::new(Disk::new(0.2), 0.1, 0.0);
+        let mesh = Mesh::from_sdf(&extruded, Vec3::splat(-0.3), Vec3::splat(0.3), CELL);
+        assert!(!mesh.triangles.is_empty());
+        for p in &mesh.positions {
+            assert!(extruded.signed_distance(*p).abs() < CELL);
+        }
+
+        // watertight: every edge is used once in each direction
+        let mut edges = HashMap::<(u32, u32), i32>::new();
+        for [a, b, c] in &mesh.triangles {
+            for (from, to) in [(*a, *b), (*b, *c), (*c, *a)] {
+                *edges.entry((from.min(to), from.max(to))).or_default() +=
+                    if from < to { 1 } else { -1 };
+            }
+        }
+        assert!(edges.values().all(|count| *count == 0));
+
+        // the box closes shapes crossing it
+        let clipped = Mesh::from_sdf(&extruded, vec3(0.0, -0.3, -0.3), Vec3::splat(0.3), CELL);
+        assert!(clipped.positions.iter().all(|p| p.x > -CELL));
+
+        let mut stl = Vec::new();
+        mesh.write_stl(&mut stl).unwrap();
+        assert_eq!(stl.len(), 84 + 50 * mesh.triangles.len());
+        let mut obj = Vec::new();
+        mesh.write_obj(&mut obj).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert_eq!(
+            obj.lines().filter(|l| l.starts_with("f ")).count(),
+            mesh.triangles.len()
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! Deterministic 2D noise, built on integer hashing so that the same seed gives the same pattern
+//! on the CPU and in shaders.
+
+use crate::interval::{Aabb, Interval};
+use crate::sdf::{Sdf, SignedDistance};
+use glam::{vec2, Vec2};
+
+const FRAC_1_SQRT_2: f32 = core::f32::consts::FRAC_1_SQRT_2;
+
+/// Integer hash with good avalanche (lowbias32)
+pub fn hash(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb_352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846c_a68b);
+    x ^= x >> 16;
+    x
+}
+
+/// Hash of a lattice point
+fn hash2(ix: i32, iy: i32, seed: u32) -> u32 {
+    hash((ix as u32).wrapping_add(hash((iy as u32).wrapping_add(hash(seed)))))
+}
+
+/// Value in [-1, 1] from a hash
+fn to_signed_unit(h: u32) -> f32 {
+    (h >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+}
+
+/// One of 8 unit gradient directions
+fn gradient(h: u32) -> Vec2 {
+    match h & 7 {
+        0 => vec2(1.0, 0.0),
+        1 => vec2(-1.0, 0.0),
+        2 => vec2(0.0, 1.0),
+        3 => vec2(0.0, -1.0),
+        4 => vec2(FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        5 => vec2(-FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        6 => vec2(FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+        _ => vec2(-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+    }
+}
+
+fn smooth(t: Vec2) -> Vec2 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn quintic(t: Vec2) -> Vec2 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn mix(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Smoothly interpolated random values on the integer lattice, in [-1, 1]
+pub fn value(p: Vec2, seed: u32) -> f32 {
+    let i = p.floor();
+    let (ix, iy) = (i.x as i32, i.y as i32);
+    let t = smooth(p - i);
+    let v00 = to_signed_unit(hash2(ix, iy, seed));
+    let v10 = to_signed_unit(hash2(ix + 1, iy, seed));
+    let v01 = to_signed_unit(hash2(ix, iy + 1, seed));
+    let v11 = to_signed_unit(hash2(ix + 1, iy + 1, seed));
+    mix(mix(v00, v10, t.x), mix(v01, v11, t.x), t.y)
+}
+
+/// Perlin gradient noise, approximately in [-1, 1]
+pub fn perlin(p: Vec2, seed: u32) -> f32 {
+    let i = p.floor();
+    let (ix, iy) = (i.x as i32, i.y as i32);
+    let f = p - i;
+    let t = quintic(f);
+    let g00 = gradient(hash2(ix, iy, seed)).dot(f);
+    let g10 = gradient(hash2(ix + 1, iy, seed)).dot(f - vec2(1.0, 0.0));
+    let g01 = gradient(hash2(ix, iy + 1, seed)).dot(f - vec2(0.0, 1.0));
+    let g11 = gradient(hash2(ix + 1, iy + 1, seed)).dot(f - vec2(1.0, 1.0));
+    // the extrema of 2D Perlin noise are at ±√½
+    core::f32::consts::SQRT_2 * mix(mix(g00, g10, t.x), mix(g01, g11, t.x), t.y)
+}
+
+/// Simplex noise, approximately in [-1, 1]
+pub fn simplex(p: Vec2, seed: u32) -> f32 {
+    // skewing factors for 2D: (√3 - 1) / 2 and (3 - √3) / 6
+    const F2: f32 = 0.366_025_42;
+    const G2: f32 = 0.211_324_87;
+
+    let s = (p.x + p.y) * F2;
+    let i = (p + s).floor();
+    let (ix, iy) = (i.x as i32, i.y as i32);
+    let x0 = p - (i - (i.x + i.y) * G2);
+    let (i1, j1) = if x0.x > x0.y { (1, 0) } else { (0, 1) };
+    let x1 = x0 - vec2(i1 as f32, j1 as f32) + G2;
+    let x2 = x0 - 1.0 + 2.0 * G2;
+
+    let n = simplex_corner(x0, hash2(ix, iy, seed))
+        + simplex_corner(x1, hash2(ix + i1, iy + j1, seed))
+        + simplex_corner(x2, hash2(ix + 1, iy + 1, seed));
+    70.0 * n
+}
+
+/// Contribution of a simplex corner at offset `x` from `p`
+fn simplex_corner(x: Vec2, h: u32) -> f32 {
+    let t = 0.5 - x.length_squared();
+    if t > 0.0 {
+        let t2 = t * t;
+        t2 * t2 * gradient(h).dot(x)
+    } else {
+        0.0
+    }
+}
+
+#[cfg_attr(feature = "strum", derive(strum::EnumIter, strum::IntoStaticStr))]
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum NoiseKind {
+    Value,
+    Perlin,
+    Simplex,
+}
+
+impl NoiseKind {
+    pub fn sample(&self, p: Vec2, seed: u32) -> f32 {
+        match self {
+            NoiseKind::Value => value(p, seed),
+            NoiseKind::Perlin => perlin(p, seed),
+            NoiseKind::Simplex => simplex(p, seed),
+        }
+    }
+}
+
+/// Fractal sum of octaves of noise
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Noise {
+    pub kind: NoiseKind,
+    /// Number of features per unit of length of the first octave
+    pub frequency: f32,
+    /// Largest possible value of the sum
+    pub amplitude: f32,
+    /// Each octave doubles the frequency and halves the weight of the previous one
+    pub octaves: u32,
+    pub seed: u32,
+}
+
+impl Noise {
+    pub const fn new(
+        kind: NoiseKind,
+        frequency: f32,
+        amplitude: f32,
+        octaves: u32,
+        seed: u32,
+    ) -> Self {
+        Self {
+            kind,
+            frequency,
+            amplitude,
+            octaves,
+            seed,
+        }
+    }
+
+    /// Noise at `p`, in [-amplitude, amplitude]
+    ///
+    /// Perlin and simplex noise slightly overshoot [-1, 1], so the sum is clamped to keep
+    /// intervals widened by the amplitude conservative.
+    pub fn sample(&self, p: Vec2) -> f32 {
+        let mut sum = 0.0;
+        let mut total = 0.0;
+        let mut weight = 1.0;
+        let mut q = p * self.frequency;
+        let mut octave = 0;
+        while octave < self.octaves.max(1) {
+            sum += weight * self.kind.sample(q, self.seed.wrapping_add(octave));
+            total += weight;
+            weight *= 0.5;
+            q *= 2.0;
+            octave += 1;
+        }
+        let bound = self.amplitude.abs();
+        (self.amplitude * sum / total).max(-bound).min(bound)
+    }
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self::new(NoiseKind::Perlin, 10.0, 0.01, 3, 0)
+    }
+}
+
+/// Perturb the distance of an sdf with noise, for rough edges
+///
+/// The result is no longer an exact distance, the error being at most the noise amplitude. Use
+/// [crate::sdf::Lipschitz] where a lower bound is needed.
+#[derive(Clone, Copy)]
+pub struct Displace<S> {
+    pub sdf: S,
+    pub noise: Noise,
+}
+
+impl<S> Displace<S> {
+    pub const fn new(sdf: S, noise: Noise) -> Self {
+        Self { sdf, noise }
+    }
+}
+
+impl<S: Sdf> Sdf for Displace<S> {
+    type T = S::T;
+    fn signed_distance(&self, p: Vec2) -> S::T {
+        let d = self.sdf.signed_distance(p);
+        d.with_bound(d.value() + self.noise.sample(p))
+    }
+
+    /// The noise changes faster than the position, so the default range would miss its peaks
+    fn interval(&self, aabb: Aabb) -> Interval {
+        self.sdf.interval(aabb).widen(self.noise.amplitude.abs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::Disk;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn noise() {
+        for kind in [NoiseKind::Value, NoiseKind::Perlin, NoiseKind::Simplex] {
+            let noise = Noise::new(kind, 7.0, 0.1, 4, 42);
+            let mut differs = false;
+            for i in 0..100 {
+                let p = vec2(i as f32 * 0.013 - 0.6, 0.37 - i as f32 * 0.007);
+                let v = noise.sample(p);
+                assert!(v.abs() <= 0.1, "{kind:?} out of range: {v}");
+                assert!(Noise::new(kind, 7.0, -0.1, 1, 42).sample(p).abs() <= 0.1);
+                assert_eq!(v, noise.sample(p));
+                differs |= v != Noise { seed: 43, ..noise }.sample(p);
+            }
+            assert!(differs, "{kind:?} ignores the seed");
+        }
+
+        let displaced = Displace::new(Disk::new(0.1), Noise::default());
+        let d = displaced.signed_distance(vec2(0.0, 0.4));
+        assert_approx_eq!(d, 0.3, Noise::default().amplitude);
+
+        // the steep noise of high frequencies stays within the range of the distances
+        let rough = Displace::new(
+            Disk::new(0.1),
+            Noise::new(NoiseKind::Value, 200.0, -0.05, 2, 7),
+        );
+        let aabb = Aabb::new(vec2(0.05, -0.02), vec2(0.09, 0.02));
+        let range = rough.interval(aabb);
+        for i in 0..=20 {
+            for j in 0..=20 {
+                let p = aabb.min + aabb.size() * vec2(i as f32, j as f32) / 20.0;
+                let d = rough.signed_distance(p);
+                assert!(range.min <= d && d <= range.max, "{d} not in {range:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Bands around the contour of a shape, optionally dashed
+
+use crate::sdf::{Sdf, SignedDistance};
+use glam::{vec2, Vec2};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Outline {
+    /// Distance of the middle of the band from the contour, positive outwards
+    pub offset: f32,
+    pub width: f32,
+    /// Length of the dashes along the contour, 0 for a solid band
+    pub dash: f32,
+    /// Length of the gaps between dashes
+    pub gap: f32,
+}
+
+impl Outline {
+    pub const fn new(offset: f32, width: f32, dash: f32, gap: f32) -> Self {
+        Self {
+            offset,
+            width,
+            dash,
+            gap,
+        }
+    }
+
+    pub fn is_dashed(&self) -> bool {
+        self.dash > 0.0 && self.gap > 0.0
+    }
+
+    /// Distance to the band, given the distance `d` to the contour and the position `arc` along
+    /// it. Dashes have square ends.
+    pub fn apply(&self, d: f32, arc: f32) -> f32 {
+        let band = (d - self.offset).abs() - 0.5 * self.width;
+        if !self.is_dashed() {
+            return band;
+        }
+        let period = self.dash + self.gap;
+        let s = arc - period * (arc / period).floor();
+        let along = (s - 0.5 * self.dash).abs() - 0.5 * self.dash;
+        // rectangle distance, with the dash length and the band width as the sides
+        vec2(band.max(0.0), along.max(0.0)).length() + band.max(along).min(0.0)
+    }
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Self::new(0.0, 0.005, 0.0, 0.02)
+    }
+}
+
+/// The band around the contour of an sdf described by an [Outline]
+///
+/// Dashes follow [Sdf::arc_length], so they are only evenly spaced for shapes with an exact one.
+#[derive(Clone, Copy)]
+pub struct Stroke<S> {
+    pub sdf: S,
+    pub outline: Outline,
+}
+
+impl<S> Stroke<S> {
+    pub const fn new(sdf: S, outline: Outline) -> Self {
+        Self { sdf, outline }
+    }
+}
+
+impl<S: Sdf> Sdf for Stroke<S> {
+    type T = S::T;
+    fn signed_distance(&self, p: Vec2) -> S::T {
+        let d = self.sdf.signed_distance(p);
+        let arc = if self.outline.is_dashed() {
+            self.sdf.arc_length(p)
+        } else {
+            0.0
+        };
+        let band = self.outline.apply(d.value(), arc);
+        if self.outline.is_dashed() {
+            d.with_bound(band)
+        } else {
+            d.with_new_distance(band)
+        }
+    }
+
+    fn arc_length(&self, p: Vec2) -> f32 {
+        self.sdf.arc_length(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::{Disk, LineSegment};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn outline() {
+        let segment = LineSegment::new(vec2(0.0, 0.0), vec2(1.0, 0.0));
+        assert_approx_eq!(segment.arc_length(vec2(0.3, 0.2)), 0.3);
+        let disk = Disk::new(0.2);
+        assert_approx_eq!(
+            disk.arc_length(vec2(0.0, 0.5)),
+            0.2 * core::f32::consts::FRAC_PI_2
+        );
+
+        let solid = Stroke::new(disk, Outline::new(0.0, 0.02, 0.0, 0.0));
+        assert_approx_eq!(solid.signed_distance(vec2(0.2, 0.0)), -0.01);
+        assert_approx_eq!(solid.signed_distance(vec2(0.0, 0.0)), 0.19);
+
+        // dashes of 0.1 every 0.2 along the segment, starting at its origin
+        let dashed = Stroke::new(segment, Outline::new(0.0, 0.02, 0.1, 0.1));
+        assert_approx_eq!(dashed.signed_distance(vec2(0.05, 0.0)), -0.01);
+        assert_approx_eq!(dashed.signed_distance(vec2(0.15, 0.0)), 0.05);
+        assert_approx_eq!(dashed.signed_distance(vec2(0.25, 0.0)), -0.01);
+    }
+}
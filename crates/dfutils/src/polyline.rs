@@ -0,0 +1,58 @@
+//! Simplification of polylines, like the strokes drawn with the mouse, which have many more
+//! points than their shape needs
+
+use crate::primitives::{CapsuleChain, MAX_POINTS};
+use glam::Vec2;
+
+/// Distance from `p` to the segment from `a` to `b`
+fn segment_distance(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared == 0.0 {
+        return p.distance(a);
+    }
+    p.distance(a + ab * ((p - a).dot(ab) / length_squared).clamp(0.0, 1.0))
+}
+
+/// Indices of the points kept by the Ramer-Douglas-Peucker algorithm, which drops the points
+/// closer than `tolerance` to the simplified polyline, always keeping the ends
+pub fn simplify(points: &[Vec2], tolerance: f32) -> Vec<usize> {
+    let n = points.len();
+    if n < 3 {
+        return (0..n).collect();
+    }
+    let mut keep = vec![false; n];
+    keep[0] = true;
+    keep[n - 1] = true;
+    let mut ranges = vec![(0, n - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let farthest = (first + 1..last)
+            .map(|i| (i, segment_distance(points[i], points[first], points[last])))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((i, d)) = farthest {
+            if d > tolerance {
+                keep[i] = true;
+                ranges.push((first, i));
+                ranges.push((i, last));
+            }
+        }
+    }
+    (0..n).filter(|i| keep[*i]).collect()
+}
+
+impl CapsuleChain {
+    /// A chain through the points of a stroke simplified by [simplify], the tolerance being
+    /// raised until at most [MAX_POINTS] are left
+    pub fn from_stroke(points: &[Vec2], radii: &[f32], tolerance: f32) -> Self {
+        let mut tolerance = tolerance.max(f32::EPSILON);
+        loop {
+            let kept = simplify(points, tolerance);
+            if kept.len() <= MAX_POINTS {
+                let points: Vec<Vec2> = kept.iter().map(|i| points[*i]).collect();
+                let radii: Vec<f32> = kept.iter().map(|i| radii[*i]).collect();
+                return Self::new(&points, &radii);
+            }
+            tolerance *= 1.5;
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Cleaning up of baked fields before export, like the fields of imported images or of
+//! compositions whose operators only give bounds of the distance
+
+use crate::{grid::Grid, jfa};
+use glam::{uvec2, vec2, Vec2, Vec4, Vec4Swizzles};
+
+impl Grid<f32> {
+    /// Replace the distances by the Euclidean distances to the zero set, keeping the signs, like
+    /// [Grid::exactify] but propagating the closest contour points by [jfa], which takes a few
+    /// passes over the grid run in parallel, at the cost of rare errors of a fraction of a pixel
+    pub fn redistance(&mut self) {
+        #[cfg(feature = "rayon")]
+        use rayon::prelude::*;
+
+        if self.w == 0 || self.h == 0 {
+            return;
+        }
+        let size = uvec2(self.w as u32, self.h as u32);
+        let pixel = self.pixel_size();
+        let at = |i: usize| uvec2(i as u32 % size.x, i as u32 / size.x);
+        let mut seeds: Vec<Vec4> = self
+            .contour_points()
+            .iter()
+            .map(|c| match c {
+                Some(c) => (*c / pixel).extend(jfa::NONE).extend(jfa::NONE),
+                None => Vec4::splat(jfa::NONE),
+            })
+            .collect();
+        for s in jfa::steps(size) {
+            #[cfg(feature = "rayon")]
+            let pixels = (0..seeds.len()).into_par_iter();
+            #[cfg(not(feature = "rayon"))]
+            let pixels = 0..seeds.len();
+            seeds = pixels
+                .map(|i| jfa::step(&seeds, size, at(i), s, pixel))
+                .collect();
+        }
+        let closest: Vec<Option<Vec2>> = seeds
+            .iter()
+            .map(|s| (s.x != jfa::NONE).then(|| s.xy() * pixel))
+            .collect();
+        self.set_closest(&closest);
+    }
+
+    /// Average over the pixels within `radius` along each axis, in world units, doing nothing
+    /// unless it is positive
+    pub fn box_blur(&mut self, radius: f32) {
+        if radius <= 0.0 {
+            return;
+        }
+        let kernel = |pixel: f32| {
+            let n = (radius / pixel).round() as usize;
+            vec![1.0; 2 * n + 1]
+        };
+        let pixel = self.pixel_size();
+        self.convolve(&kernel(pixel.x), &kernel(pixel.y));
+    }
+
+    /// Gaussian blur with a standard deviation of `sigma`, in world units, doing nothing unless
+    /// it is positive
+    pub fn gaussian_blur(&mut self, sigma: f32) {
+        if sigma <= 0.0 {
+            return;
+        }
+        let kernel = |pixel: f32| {
+            let sigma = sigma / pixel;
+            let n = (3.0 * sigma).ceil() as isize;
+            (-n..=n)
+                .map(|i| (-0.5 * (i as f32 / sigma).powi(2)).exp())
+                .collect::<Vec<_>>()
+        };
+        let pixel = self.pixel_size();
+        self.convolve(&kernel(pixel.x), &kernel(pixel.y));
+    }
+
+    /// Convolve the rows with `kx` and the columns with `ky`, normalized kernels of odd
+    /// lengths, the pixels on the edges extending beyond the grid
+    fn convolve(&mut self, kx: &[f32], ky: &[f32]) {
+        let (w, h) = (self.w, self.h);
+        let sum = |k: &[f32]| k.iter().sum::<f32>();
+        let (sx, sy) = (sum(kx), sum(ky));
+        let (nx, ny) = (kx.len() / 2, ky.len() / 2);
+        let rows = self.buffer.clone();
+        for y in 0..h {
+            for x in 0..w {
+                self.buffer[y * w + x] = kx
+                    .iter()
+                    .enumerate()
+                    .map(|(i, k)| k * rows[y * w + (x + i).saturating_sub(nx).min(w - 1)])
+                    .sum::<f32>()
+                    / sx;
+            }
+        }
+        let columns = self.buffer.clone();
+        for y in 0..h {
+            for x in 0..w {
+                self.buffer[y * w + x] = ky
+                    .iter()
+                    .enumerate()
+                    .map(|(i, k)| k * columns[(y + i).saturating_sub(ny).min(h - 1) * w + x])
+                    .sum::<f32>()
+                    / sy;
+            }
+        }
+    }
+
+    /// Grow the shapes by `amount`, or shrink them if it is negative
+    pub fn offset(&mut self, amount: f32) {
+        for d in &mut self.buffer {
+            *d -= amount;
+        }
+    }
+
+    /// Whether each pixel is inside the level set of `level`, like a shape offset by `-level`
+    pub fn threshold(&self, level: f32) -> Grid<bool> {
+        self.map(|d| d < level)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.zip(other, f32::min)
+    }
+
+    pub fn intersect(&self, other: &Self) -> Self {
+        self.zip(other, f32::max)
+    }
+
+    /// Gradients by finite differences, with y up like the world, one-sided on the edges
+    pub fn gradient(&self) -> Grid<Vec2> {
+        let (w, h) = (self.w, self.h);
+        let pixel = self.pixel_size();
+        let derivative = |a: f32, b: f32, steps: usize, pixel: f32| {
+            if steps == 0 {
+                0.0
+            } else {
+                (b - a) / (steps as f32 * pixel)
+            }
+        };
+        let mut result = Grid {
+            w,
+            h,
+            bounds: self.bounds,
+            buffer: vec![Vec2::ZERO; w * h],
+        };
+        for y in 0..h {
+            for x in 0..w {
+                let (x0, x1) = (x.saturating_sub(1), (x + 1).min(w - 1));
+                let (y0, y1) = (y.saturating_sub(1), (y + 1).min(h - 1));
+                result.buffer[y * w + x] = vec2(
+                    derivative(self.get(x0, y), self.get(x1, y), x1 - x0, pixel.x),
+                    // rows go down
+                    derivative(self.get(x, y1), self.get(x, y0), y1 - y0, pixel.y),
+                );
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn postprocess() {
+        const W: usize = 80;
+        const H: usize = 60;
+
+        let disk = Grid::from_sdf(W, H, &Disk::new(0.2));
+        let pixel = disk.pixel_size().x;
+
+        // a field with the right contour but wrong distances away from it
+        let mut scaled = disk.map(|d| if d < 0.0 { 0.5 * d } else { 3.0 * d });
+        scaled.redistance();
+        let mut exactified = disk.map(|d| if d < 0.0 { 0.5 * d } else { 3.0 * d });
+        exactified.exactify();
+        for ((d, expected), e) in scaled
+            .buffer
+            .iter()
+            .zip(&disk.buffer)
+            .zip(&exactified.buffer)
+        {
+            assert_approx_eq!(d, expected, 0.5 * pixel);
+            assert_approx_eq!(d, e, 0.5 * pixel);
+        }
+
+        let mut offset = disk.clone();
+        offset.offset(0.1);
+        let larger = Grid::from_sdf(W, H, &Disk::new(0.3));
+        for (d, expected) in offset.buffer.iter().zip(&larger.buffer) {
+            assert_approx_eq!(d, expected, 1e-5);
+        }
+        let inside = disk.threshold(0.0);
+        assert!(inside.get(W / 2, H / 2));
+        assert!(!inside.get(0, 0));
+
+        let torus = Grid::from_sdf(W, H, &Torus::new(0.3, 0.05));
+        for (i, d) in disk.union(&torus).buffer.iter().enumerate() {
+            assert_eq!(*d, disk.buffer[i].min(torus.buffer[i]));
+        }
+        for (i, d) in disk.intersect(&torus).buffer.iter().enumerate() {
+            assert_eq!(*d, disk.buffer[i].max(torus.buffer[i]));
+        }
+
+        // blurs keep linear fields, away from the edges
+        let plane = Grid::from_sdf(W, H, &Plane::new(vec2(0.6, 0.8)));
+        let mut blurred = plane.clone();
+        blurred.gaussian_blur(2.0 * pixel);
+        blurred.box_blur(2.0 * pixel);
+        for y in 10..H - 10 {
+            for x in 10..W - 10 {
+                assert_approx_eq!(blurred.get(x, y), plane.get(x, y), 1e-4);
+            }
+        }
+        let mut unblurred = plane.clone();
+        unblurred.gaussian_blur(0.0);
+        unblurred.box_blur(-pixel);
+        assert_eq!(unblurred.buffer, plane.buffer);
+
+        let gradient = plane.gradient();
+        for g in &gradient.buffer {
+            assert!(g.abs_diff_eq(vec2(0.6, 0.8), 1e-3));
+        }
+        let gradient = disk.gradient();
+        let (x, y) = (W / 2 + 20, H / 2 - 10);
+        let direction = disk.position(x, y).normalize();
+        assert!(gradient.get(x, y).abs_diff_eq(direction, 0.02));
+    }
+}
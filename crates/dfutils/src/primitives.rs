@@ -1,3 +1,4 @@
+mod capsule_chain;
 mod cross;
 mod disk;
 mod line_segment;
@@ -6,6 +7,7 @@ mod ray;
 mod rectangle;
 mod torus;
 
+pub use capsule_chain::*;
 pub use cross::*;
 pub use disk::*;
 pub use line_segment::*;
@@ -13,3 +15,21 @@ pub use plane::*;
 pub use ray::*;
 pub use rectangle::*;
 pub use torus::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sdf::Sdf;
+    use assert_approx_eq::assert_approx_eq;
+    use glam::vec2;
+
+    #[test]
+    fn primitives() {
+        let disk = Disk::new(0.1);
+        let d = disk.signed_distance(vec2(0.0, 0.4));
+        assert_approx_eq!(d, 0.3);
+        let torus = Torus::new(0.2, 0.1);
+        let d = torus.signed_distance(vec2(0.4, 0.0));
+        assert_approx_eq!(d, 0.1);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::sdf::Sdf;
+use glam::{vec2, Vec2};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+/// Most points of a [CapsuleChain], which is stored inline so that shapes stay `Copy`
+///
+/// Each point adds 12 bytes to every shape, chains being the largest ones.
+pub const MAX_POINTS: usize = 16;
+
+/// A polyline with a radius at each point, like a brush stroke: the union of the convex hulls
+/// of the disks at the ends of each segment
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct CapsuleChain {
+    /// The first `len` are used, the others are zero
+    pub points: [Vec2; MAX_POINTS],
+    pub radii: [f32; MAX_POINTS],
+    pub len: u32,
+}
+
+impl CapsuleChain {
+    /// A chain through `points`, with the matching `radii`, of which there must be as many and
+    /// at most [MAX_POINTS]
+    ///
+    /// Longer polylines are cut short, [CapsuleChain::from_stroke] simplifies them instead.
+    pub fn new(points: &[Vec2], radii: &[f32]) -> Self {
+        debug_assert!(!points.is_empty() && points.len() == radii.len());
+        debug_assert!(points.len() <= MAX_POINTS);
+        let mut result = Self {
+            points: [Vec2::ZERO; MAX_POINTS],
+            radii: [0.0; MAX_POINTS],
+            len: points.len().min(MAX_POINTS) as u32,
+        };
+        let len = result.len as usize;
+        result.points[..len].copy_from_slice(&points[..len]);
+        result.radii[..len].copy_from_slice(&radii[..len]);
+        result
+    }
+
+    pub fn points(&self) -> &[Vec2] {
+        &self.points[..self.len as usize]
+    }
+
+    pub fn radii(&self) -> &[f32] {
+        &self.radii[..self.len as usize]
+    }
+
+    /// Distance to segment `i`, from point `i` to point `i + 1`
+    fn segment_distance(&self, i: usize, p: Vec2) -> f32 {
+        uneven_capsule(
+            p,
+            self.points[i],
+            self.points[i + 1],
+            self.radii[i],
+            self.radii[i + 1],
+        )
+    }
+}
+
+impl Default for CapsuleChain {
+    fn default() -> Self {
+        Self::new(
+            &[
+                vec2(-0.3, -0.1),
+                vec2(-0.1, 0.1),
+                vec2(0.1, -0.1),
+                vec2(0.3, 0.1),
+            ],
+            &[0.04, 0.07, 0.07, 0.04],
+        )
+    }
+}
+
+/// Signed distance to the convex hull of the disks of radius `ra` around `a` and `rb` around
+/// `b`, after Inigo Quilez
+fn uneven_capsule(p: Vec2, a: Vec2, b: Vec2, ra: f32, rb: f32) -> f32 {
+    let p = p - a;
+    let b = b - a;
+    let h = b.length_squared();
+    let dr = ra - rb;
+    if dr * dr >= h {
+        // one disk contains the other
+        return (p.length() - ra).min(p.distance(b) - rb);
+    }
+    // coordinates across and along the segment, in units of its length
+    let q = vec2(p.perp_dot(b).abs(), p.dot(b)) / h;
+    let c = vec2((h - dr * dr).sqrt(), dr);
+    let k = c.perp_dot(q);
+    if k < 0.0 {
+        (h * q.length_squared()).sqrt() - ra
+    } else if k > c.x {
+        (h * (q.length_squared() + 1.0 - 2.0 * q.y)).sqrt() - rb
+    } else {
+        c.dot(q) - ra
+    }
+}
+
+impl Sdf for CapsuleChain {
+    type T = f32;
+    fn signed_distance(&self, p: Vec2) -> f32 {
+        let mut d = p.distance(self.points[0]) - self.radii[0];
+        for i in 1..self.len as usize {
+            d = d.min(self.segment_distance(i - 1, p));
+        }
+        d
+    }
+
+    /// Exact, measured along the polyline from the first point, at the projection of `p` on the
+    /// nearest segment
+    fn arc_length(&self, p: Vec2) -> f32 {
+        let mut result = 0.0;
+        let mut nearest = f32::INFINITY;
+        let mut start = 0.0;
+        for i in 1..self.len as usize {
+            let (a, b) = (self.points[i - 1], self.points[i] - self.points[i - 1]);
+            let length = b.length();
+            let d = self.segment_distance(i - 1, p);
+            if d < nearest {
+                nearest = d;
+                result = if length > 0.0 {
+                    start + ((p - a).dot(b) / length).clamp(0.0, length)
+                } else {
+                    start
+                };
+            }
+            start += length;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polyline;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn capsule_chain() {
+        let chain = CapsuleChain::new(
+            &[vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(1.0, 1.0)],
+            &[0.1, 0.2, 0.1],
+        );
+        assert_approx_eq!(chain.signed_distance(vec2(-0.5, 0.0)), 0.4);
+        assert_approx_eq!(chain.signed_distance(vec2(1.5, 0.0)), 0.3);
+        assert_approx_eq!(chain.signed_distance(vec2(1.0, 1.5)), 0.4);
+        assert!(chain.signed_distance(vec2(0.5, 0.1)) < 0.0);
+        assert_approx_eq!(chain.arc_length(vec2(0.5, -0.3)), 0.5);
+        assert_approx_eq!(chain.arc_length(vec2(1.2, 0.5)), 1.5);
+
+        // strokes lose the points along straight lines
+        let stroke: Vec<Vec2> = (0..=100)
+            .map(|i| {
+                let t = i as f32 / 100.0;
+                if t < 0.5 {
+                    vec2(t, 0.0)
+                } else {
+                    vec2(0.5, t - 0.5)
+                }
+            })
+            .collect();
+        assert_eq!(polyline::simplify(&stroke, 1e-3), vec![0, 50, 100]);
+        assert_eq!(polyline::simplify(&stroke, 1.0), vec![0, 100]);
+        let wiggly: Vec<Vec2> = (0..1000)
+            .map(|i| vec2(i as f32 / 1000.0, 0.1 * (i as f32).sin()))
+            .collect();
+        let chain = CapsuleChain::from_stroke(&wiggly, &[0.05; 1000], 0.0);
+        assert!(chain.len as usize <= MAX_POINTS);
+        assert_eq!(chain.points()[0], wiggly[0]);
+        assert_eq!(chain.points()[chain.len as usize - 1], wiggly[999]);
+    }
+}
@@ -4,6 +4,7 @@ use glam::*;
 use num_traits::Float;
 
 #[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, PartialEq)]
 pub struct Cross {
     pub length: f32,
@@ -1,7 +1,14 @@
-use crate::sdf::Sdf;
-use glam::Vec2;
+use crate::{
+    interval::{Aabb, Interval},
+    lanes,
+    sdf::Sdf,
+};
+use glam::{Vec2, Vec4};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 
 #[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, PartialEq)]
 pub struct Disk {
     pub radius: f32,
@@ -24,4 +31,16 @@ impl Sdf for Disk {
     fn signed_distance(&self, p: Vec2) -> f32 {
         p.length() - self.radius
     }
+
+    fn signed_distance_many(&self, points: &[Vec2], out: &mut [f32]) {
+        lanes::map(points, out, |p| p.length() - Vec4::splat(self.radius));
+    }
+
+    fn interval(&self, aabb: Aabb) -> Interval {
+        aabb.length() - self.radius
+    }
+
+    fn arc_length(&self, p: Vec2) -> f32 {
+        p.y.atan2(p.x) * self.radius
+    }
 }
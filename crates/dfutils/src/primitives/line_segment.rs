@@ -1,7 +1,11 @@
-use crate::sdf::Sdf;
-use glam::{vec2, Vec2};
+use crate::{
+    lanes::{self, Vec2x4},
+    sdf::Sdf,
+};
+use glam::{vec2, Vec2, Vec4};
 
 #[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, PartialEq)]
 pub struct LineSegment {
     pub a: Vec2,
@@ -30,7 +34,29 @@ impl Sdf for LineSegment {
         p.distance(self.a + b * ((p - self.a).dot(b) / b.length_squared()).clamp(0.0, 1.0))
     }
 
+    fn signed_distance_many(&self, points: &[Vec2], out: &mut [f32]) {
+        let b = self.b - self.a;
+        let inv_length_squared = 1.0 / b.length_squared();
+        let segment = Vec2x4::splat(b);
+        lanes::map(points, out, |p| {
+            let p = p - self.a;
+            let h = (p.dot(b) * inv_length_squared).clamp(Vec4::ZERO, Vec4::ONE);
+            (p - segment.scale(h)).length()
+        });
+    }
+
     fn distance(&self, p: Vec2) -> f32 {
         self.signed_distance(p)
     }
+
+    /// Exact, measured from `a`
+    fn arc_length(&self, p: Vec2) -> f32 {
+        let b = self.b - self.a;
+        let length = b.length();
+        if length > 0.0 {
+            ((p - self.a).dot(b) / length).clamp(0.0, length)
+        } else {
+            0.0
+        }
+    }
 }
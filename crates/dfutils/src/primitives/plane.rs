@@ -1,7 +1,12 @@
-use crate::sdf::Sdf;
+use crate::{
+    interval::{Aabb, Interval},
+    lanes,
+    sdf::Sdf,
+};
 use glam::Vec2;
 
 #[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, PartialEq)]
 pub struct Plane {
     pub normal: Vec2,
@@ -24,4 +29,18 @@ impl Sdf for Plane {
     fn signed_distance(&self, p: Vec2) -> f32 {
         self.normal.dot(p)
     }
+
+    fn signed_distance_many(&self, points: &[Vec2], out: &mut [f32]) {
+        lanes::map(points, out, |p| p.dot(self.normal));
+    }
+
+    fn interval(&self, aabb: Aabb) -> Interval {
+        let radius = self.normal.abs().dot(0.5 * aabb.size());
+        Interval::around(self.normal.dot(aabb.center()), radius)
+    }
+
+    /// Exact, measured from the origin
+    fn arc_length(&self, p: Vec2) -> f32 {
+        self.normal.perp_dot(p)
+    }
 }
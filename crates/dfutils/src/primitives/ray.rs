@@ -1,7 +1,11 @@
-use crate::sdf::Sdf;
-use glam::Vec2;
+use crate::{
+    lanes::{self, Vec2x4},
+    sdf::Sdf,
+};
+use glam::{Vec2, Vec4};
 
 #[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, PartialEq)]
 pub struct Ray {
     pub direction: Vec2,
@@ -25,7 +29,19 @@ impl Sdf for Ray {
         p.distance(self.direction * p.dot(self.direction).max(0.0))
     }
 
+    fn signed_distance_many(&self, points: &[Vec2], out: &mut [f32]) {
+        let direction = Vec2x4::splat(self.direction);
+        lanes::map(points, out, |p| {
+            (p - direction.scale(p.dot(self.direction).max(Vec4::ZERO))).length()
+        });
+    }
+
     fn distance(&self, p: Vec2) -> f32 {
         self.signed_distance(p)
     }
+
+    /// Exact, measured from the origin
+    fn arc_length(&self, p: Vec2) -> f32 {
+        p.dot(self.direction).max(0.0)
+    }
 }
@@ -1,7 +1,12 @@
-use crate::sdf::Sdf;
-use glam::{vec2, Vec2};
+use crate::{
+    interval::{Aabb, Interval},
+    lanes,
+    sdf::Sdf,
+};
+use glam::{vec2, Vec2, Vec4};
 
 #[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, PartialEq)]
 pub struct Rectangle {
     pub width: f32,
@@ -30,6 +35,26 @@ impl Sdf for Rectangle {
         p.max(Vec2::ZERO).length() + p.min(Vec2::ZERO).max_element()
     }
 
+    fn signed_distance_many(&self, points: &[Vec2], out: &mut [f32]) {
+        let half = vec2(self.width, self.height) * 0.5;
+        lanes::map(points, out, |p| {
+            let q = p.abs() - half;
+            let outside = lanes::Vec2x4::new(q.x.max(Vec4::ZERO), q.y.max(Vec4::ZERO));
+            outside.length() + q.x.max(q.y).min(Vec4::ZERO)
+        });
+    }
+
+    /// The distance only grows with `|x|` and `|y|`, so its range comes from the nearest and
+    /// farthest corners of the box after folding it into the first quadrant
+    fn interval(&self, aabb: Aabb) -> Interval {
+        let nearest = Vec2::ZERO.clamp(aabb.min, aabb.max).abs();
+        let farthest = aabb.min.abs().max(aabb.max.abs());
+        Interval::new(
+            self.signed_distance(nearest),
+            self.signed_distance(farthest),
+        )
+    }
+
     fn distance(&self, p: Vec2) -> f32 {
         let p = p.abs() - vec2(self.width, self.height) * 0.5;
         p.max(Vec2::ZERO).length() - p.min(Vec2::ZERO).max_element()
@@ -1,8 +1,15 @@
 use super::Disk;
-use crate::sdf::Sdf;
-use glam::Vec2;
+use crate::{
+    interval::{Aabb, Interval},
+    lanes,
+    sdf::Sdf,
+};
+use glam::{Vec2, Vec4};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 
 #[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, PartialEq)]
 pub struct Torus {
     pub major_radius: f32,
@@ -32,4 +39,18 @@ impl Sdf for Torus {
     fn signed_distance(&self, p: Vec2) -> f32 {
         Disk::new(self.major_radius).distance(p) - self.minor_radius
     }
+
+    fn signed_distance_many(&self, points: &[Vec2], out: &mut [f32]) {
+        lanes::map(points, out, |p| {
+            (p.length() - Vec4::splat(self.major_radius)).abs() - Vec4::splat(self.minor_radius)
+        });
+    }
+
+    fn interval(&self, aabb: Aabb) -> Interval {
+        (aabb.length() - self.major_radius).abs() - self.minor_radius
+    }
+
+    fn arc_length(&self, p: Vec2) -> f32 {
+        p.y.atan2(p.x) * self.major_radius
+    }
 }
@@ -1,8 +1,13 @@
-use crate::{primitives::*, sdf::Sdf};
+use crate::{
+    interval::{Aabb, Interval, Prune},
+    primitives::*,
+    sdf::Sdf,
+};
 use glam::Vec2;
 
 #[cfg_attr(feature = "strum", derive(strum::EnumIter, strum::IntoStaticStr))]
 #[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, PartialEq)]
 #[enum_delegate::implement(Sdf)]
 pub enum Shape {
@@ -13,4 +18,57 @@ pub enum Shape {
     Plane(Plane),
     Ray(Ray),
     LineSegment(LineSegment),
+    CapsuleChain(CapsuleChain),
+}
+
+impl Prune for Shape {
+    type Pruned = Shape;
+    fn prune(&self, aabb: Aabb) -> (Shape, Interval) {
+        (*self, self.interval(aabb))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn lipschitz(shape in shape(), p in point(), q in point()) {
+            check_bounds(&shape, p, q)?;
+        }
+
+        #[test]
+        fn sign(shape in shape(), p in point()) {
+            let d = shape.signed_distance(p);
+            prop_assume!(d.abs() > EPSILON);
+            prop_assert_eq!(d < 0.0, contains(&shape, p));
+        }
+
+        #[test]
+        fn distance(shape in shape(), p in point()) {
+            prop_assert!((shape.distance(p) - shape.signed_distance(p).abs()).abs() <= EPSILON);
+        }
+
+        #[test]
+        fn batched(shape in shape(), points in proptest::collection::vec(point(), 0..11)) {
+            let mut out = vec![0.0; points.len()];
+            shape.signed_distance_many(&points, &mut out);
+            for (p, d) in points.iter().zip(out) {
+                let expected = shape.signed_distance(*p);
+                prop_assert!((d - expected).abs() <= EPSILON, "{} != {}", d, expected);
+            }
+        }
+
+        #[test]
+        fn unit_gradient(shape in shape(), p in point()) {
+            let d = shape.signed_distance(p);
+            prop_assume!(d.abs() > 1e-2);
+            if let Some(g) = gradient(&shape, p) {
+                prop_assert!((g.length() - 1.0).abs() <= 1e-2, "gradient of length {}", g.length());
+            }
+        }
+    }
 }
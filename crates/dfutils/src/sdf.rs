@@ -1,3 +1,4 @@
+use crate::interval::{Aabb, Interval};
 use glam::{vec2, Vec2};
 #[cfg(not(feature = "std"))]
 use num_traits::Float;
@@ -12,6 +13,12 @@ where
 
     fn with_new_distance(&self, d: f32) -> Self;
 
+    /// Like [SignedDistance::with_new_distance], for a distance which is only a lower bound of
+    /// the true one, away from the contour
+    fn with_bound(&self, d: f32) -> Self {
+        self.with_new_distance(d)
+    }
+
     fn union(&self, other: &Self) -> Self {
         let a = self.value();
         let b = other.value();
@@ -45,6 +52,17 @@ where
     fn xor(&self, other: &Self) -> Self {
         self.intersect(other).subtract(&self.union(other))
     }
+
+    /// Linear interpolation of the distances, `t = 0` giving `self` and `t = 1` giving `other`.
+    /// The rest of the result comes from whichever is weighted most.
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let d = self.value() + (other.value() - self.value()) * t;
+        if t < 0.5 {
+            self.with_bound(d)
+        } else {
+            other.with_bound(d)
+        }
+    }
 }
 
 impl SignedDistance for f32 {
@@ -61,16 +79,134 @@ impl SignedDistance for f32 {
     }
 }
 
+/// Signed distances carrying data about the closest shape, like the id of the item it belongs to
+pub trait WithData: SignedDistance {
+    type Data: Copy;
+
+    fn data(&self) -> Self::Data;
+}
+
+/// A distance along with whether it is exact, rather than a lower bound of the true distance
+///
+/// Union is only exact outside and intersection inside, so subtractions are bounds outside of
+/// the result, which is where offsets and roundings of them go wrong.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Tracked<T> {
+    pub distance: T,
+    pub exact: bool,
+}
+
+impl<T> Tracked<T> {
+    pub const fn exact(distance: T) -> Self {
+        Self {
+            distance,
+            exact: true,
+        }
+    }
+}
+
+impl<T: SignedDistance> SignedDistance for Tracked<T> {
+    fn value(&self) -> f32 {
+        self.distance.value()
+    }
+
+    fn divergent() -> Self {
+        Self::exact(T::divergent())
+    }
+
+    fn with_new_distance(&self, d: f32) -> Self {
+        Self {
+            distance: self.distance.with_new_distance(d),
+            exact: self.exact,
+        }
+    }
+
+    fn with_bound(&self, d: f32) -> Self {
+        Self {
+            distance: self.distance.with_bound(d),
+            exact: false,
+        }
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        let distance = self.distance.union(&other.distance);
+        Self {
+            distance,
+            exact: self.exact && other.exact && distance.value() >= 0.0,
+        }
+    }
+
+    fn intersect(&self, other: &Self) -> Self {
+        let distance = self.distance.intersect(&other.distance);
+        Self {
+            distance,
+            exact: self.exact && other.exact && distance.value() <= 0.0,
+        }
+    }
+
+    fn subtract(&self, other: &Self) -> Self {
+        let distance = self.distance.subtract(&other.distance);
+        Self {
+            distance,
+            exact: self.exact && other.exact && distance.value() <= 0.0,
+        }
+    }
+
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let exact = if t == 0.0 {
+            self.exact
+        } else if t == 1.0 {
+            other.exact
+        } else {
+            false
+        };
+        Self {
+            distance: self.distance.lerp(&other.distance, t),
+            exact,
+        }
+    }
+}
+
 #[enum_delegate::register]
 pub trait Sdf {
     type T: SignedDistance;
     fn signed_distance(&self, p: Vec2) -> Self::T;
 
+    /// [Sdf::signed_distance] at each of `points`, into `out` which has the same length
+    ///
+    /// Implementations evaluate several points with each operation where they can, see
+    /// [lanes](crate::lanes).
+    fn signed_distance_many(&self, points: &[Vec2], out: &mut [Self::T]) {
+        for (p, d) in points.iter().zip(out) {
+            *d = self.signed_distance(*p);
+        }
+    }
+
     fn distance(&self, p: Vec2) -> Self::T {
         let result = self.signed_distance(p);
         result.with_new_distance(result.value().abs())
     }
 
+    /// Range of the distances over `aabb`
+    ///
+    /// The default holds for sdfs whose distances change no faster than the position, like exact
+    /// ones and the bounds given by operators.
+    fn interval(&self, aabb: Aabb) -> Interval {
+        let d = self.signed_distance(aabb.center()).value();
+        Interval::around(d, 0.5 * aabb.size().length())
+    }
+
+    /// Position along the contour of the point of the contour closest to `p`, as a length
+    ///
+    /// The default estimates it from the direction of the gradient, which is exact for contours
+    /// that are circles around the origin.
+    fn arc_length(&self, p: Vec2) -> f32 {
+        let g = self.derivative(p, 1e-3).normalize_or_zero();
+        let q = p - self.signed_distance(p).value() * g;
+        g.y.atan2(g.x) * q.length()
+    }
+
     fn derivative(&self, p: Vec2, h: f32) -> Vec2 {
         vec2(
             self.signed_distance(p + h * Vec2::X).value()
@@ -80,3 +216,96 @@ pub trait Sdf {
         ) / (2.0 * h)
     }
 }
+
+/// The distances of an sdf as [Tracked], `S` being exact
+#[derive(Clone, Copy)]
+pub struct Track<S>(pub S);
+
+impl<S: Sdf> Sdf for Track<S> {
+    type T = Tracked<S::T>;
+    fn signed_distance(&self, p: Vec2) -> Self::T {
+        Tracked::exact(self.0.signed_distance(p))
+    }
+
+    fn interval(&self, aabb: Aabb) -> Interval {
+        self.0.interval(aabb)
+    }
+
+    fn arc_length(&self, p: Vec2) -> f32 {
+        self.0.arc_length(p)
+    }
+}
+
+/// An sdf scaled down by a bound on the norm of its gradient, making its distances safe lower
+/// bounds for raymarching and offsetting
+#[derive(Clone, Copy)]
+pub struct Lipschitz<S> {
+    pub sdf: S,
+    pub bound: f32,
+}
+
+impl<S> Lipschitz<S> {
+    pub const fn new(sdf: S, bound: f32) -> Self {
+        Self { sdf, bound }
+    }
+}
+
+impl<S: Sdf> Sdf for Lipschitz<S> {
+    type T = S::T;
+    fn signed_distance(&self, p: Vec2) -> S::T {
+        let d = self.sdf.signed_distance(p);
+        d.with_bound(d.value() / self.bound)
+    }
+
+    fn arc_length(&self, p: Vec2) -> f32 {
+        self.sdf.arc_length(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{primitives::Disk, testing::*};
+    use assert_approx_eq::assert_approx_eq;
+    use proptest::prelude::*;
+
+    #[test]
+    fn signed_distances() {
+        let p = vec2(0.3, 0.0);
+        let a = Disk::new(0.1).signed_distance(p);
+        let b = Disk::new(0.2).signed_distance(p);
+        assert_approx_eq!(a.lerp(&b, 0.0), 0.2);
+        assert_approx_eq!(a.lerp(&b, 1.0), 0.1);
+        assert_approx_eq!(a.lerp(&b, 0.5), 0.15);
+
+        let scaled = Lipschitz::new(Track(Disk::new(0.1)), 2.0);
+        let d = scaled.signed_distance(vec2(0.4, 0.0));
+        assert_approx_eq!(d.value(), 0.15);
+        assert!(!d.exact);
+    }
+
+    proptest! {
+        #[test]
+        fn operators(a in shape(), b in shape(), offset in point(), p in point(), q in point()) {
+            let da = |p: Vec2| a.signed_distance(p);
+            let db = |p: Vec2| b.signed_distance(p - offset);
+            let inside_a = |p: Vec2| contains(&a, p);
+            let inside_b = |p: Vec2| contains(&b, p - offset);
+
+            let union = FnSdf(|p| da(p).union(&db(p)));
+            check_bounds(&union, p, q)?;
+            let intersection = FnSdf(|p| da(p).intersect(&db(p)));
+            check_bounds(&intersection, p, q)?;
+            let difference = FnSdf(|p| da(p).subtract(&db(p)));
+            check_bounds(&difference, p, q)?;
+            let xor = FnSdf(|p| da(p).xor(&db(p)));
+            check_bounds(&xor, p, q)?;
+
+            prop_assume!(da(p).abs() > EPSILON && db(p).abs() > EPSILON);
+            prop_assert_eq!(union.signed_distance(p) < 0.0, inside_a(p) || inside_b(p));
+            prop_assert_eq!(intersection.signed_distance(p) < 0.0, inside_a(p) && inside_b(p));
+            prop_assert_eq!(difference.signed_distance(p) < 0.0, !inside_a(p) && inside_b(p));
+            prop_assert_eq!(xor.signed_distance(p) < 0.0, inside_a(p) != inside_b(p));
+        }
+    }
+}
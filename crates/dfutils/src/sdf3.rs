@@ -0,0 +1,223 @@
+//! 3D distance fields built from 2D compositions, and a raymarcher to look at them
+
+use crate::sdf::{Sdf, SignedDistance};
+use glam::{vec2, vec3, Vec2, Vec3};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+pub trait Sdf3 {
+    fn signed_distance(&self, p: Vec3) -> f32;
+
+    fn normal(&self, p: Vec3, h: f32) -> Vec3 {
+        vec3(
+            self.signed_distance(p + h * Vec3::X) - self.signed_distance(p - h * Vec3::X),
+            self.signed_distance(p + h * Vec3::Y) - self.signed_distance(p - h * Vec3::Y),
+            self.signed_distance(p + h * Vec3::Z) - self.signed_distance(p - h * Vec3::Z),
+        )
+        .normalize_or_zero()
+    }
+}
+
+/// Distance to the extrusion along Z of a profile, given the distance `d` to the profile and the
+/// position `z`. Edges are rounded with `rounding`, eating into the profile.
+pub fn extrude(d: f32, z: f32, height: f32, rounding: f32) -> f32 {
+    let w = vec2(d + rounding, z.abs() - 0.5 * height + rounding);
+    w.x.max(w.y).min(0.0) + w.max(Vec2::ZERO).length() - rounding
+}
+
+/// The point of the profile of a revolution around the Y axis, the profile being moved `offset`
+/// away from the axis
+pub fn revolve(p: Vec3, offset: f32) -> Vec2 {
+    vec2(vec2(p.x, p.z).length() - offset, p.y)
+}
+
+/// A 2D sdf extruded along Z, centered on the XY plane
+#[derive(Clone, Copy)]
+pub struct Extrude<S> {
+    pub sdf: S,
+    pub height: f32,
+    pub rounding: f32,
+}
+
+impl<S> Extrude<S> {
+    pub const fn new(sdf: S, height: f32, rounding: f32) -> Self {
+        Self {
+            sdf,
+            height,
+            rounding,
+        }
+    }
+}
+
+impl<S: Sdf> Sdf3 for Extrude<S> {
+    fn signed_distance(&self, p: Vec3) -> f32 {
+        let d = self.sdf.signed_distance(vec2(p.x, p.y)).value();
+        extrude(d, p.z, self.height, self.rounding)
+    }
+}
+
+/// A 2D sdf revolved around the Y axis
+#[derive(Clone, Copy)]
+pub struct Revolve<S> {
+    pub sdf: S,
+    /// Distance of the origin of the profile from the axis
+    pub offset: f32,
+}
+
+impl<S> Revolve<S> {
+    pub const fn new(sdf: S, offset: f32) -> Self {
+        Self { sdf, offset }
+    }
+}
+
+impl<S: Sdf> Sdf3 for Revolve<S> {
+    fn signed_distance(&self, p: Vec3) -> f32 {
+        self.sdf.signed_distance(revolve(p, self.offset)).value()
+    }
+}
+
+/// A camera orbiting the origin
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Camera {
+    /// Rotation around the Y axis in radians, 0 looking down -Z
+    pub yaw: f32,
+    /// Elevation in radians
+    pub pitch: f32,
+    pub distance: f32,
+    /// Distance of the image plane, for an image of height 1
+    pub focal_length: f32,
+}
+
+impl Camera {
+    pub const fn new(yaw: f32, pitch: f32, distance: f32, focal_length: f32) -> Self {
+        Self {
+            yaw,
+            pitch,
+            distance,
+            focal_length,
+        }
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.distance
+            * vec3(
+                self.yaw.sin() * self.pitch.cos(),
+                self.pitch.sin(),
+                self.yaw.cos() * self.pitch.cos(),
+            )
+    }
+
+    /// Origin and direction of the ray through `uv`, in the coordinates of the 2D view
+    pub fn ray(&self, uv: Vec2) -> (Vec3, Vec3) {
+        let origin = self.position();
+        let forward = -origin.normalize();
+        let right = forward.cross(Vec3::Y).normalize();
+        let up = right.cross(forward);
+        let direction = (uv.x * right + uv.y * up + self.focal_length * forward).normalize();
+        (origin, direction)
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new(0.6, 0.4, 1.5, 1.5)
+    }
+}
+
+const MAX_STEPS: u32 = 128;
+const HIT_DISTANCE: f32 = 1e-4;
+
+/// Distance along the ray to the first surface, if hit before `max_distance`
+pub fn raymarch<S: Sdf3>(sdf: &S, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<f32> {
+    let mut t = 0.0;
+    let mut step = 0;
+    while step < MAX_STEPS && t < max_distance {
+        let d = sdf.signed_distance(origin + t * direction);
+        if d < HIT_DISTANCE * t.max(1.0) {
+            return Some(t);
+        }
+        t += d;
+        step += 1;
+    }
+    None
+}
+
+pub const BACKGROUND: Vec3 = vec3(0.12, 0.12, 0.14);
+
+/// Linear color of the pixel at `uv`, lit by a directional light
+pub fn shade<S: Sdf3>(sdf: &S, camera: &Camera, uv: Vec2) -> Vec3 {
+    let (origin, direction) = camera.ray(uv);
+    let Some(t) = raymarch(sdf, origin, direction, 2.0 * camera.distance + 2.0) else {
+        return BACKGROUND;
+    };
+    let normal = sdf.normal(origin + t * direction, 1e-3);
+    let light = vec3(0.4, 0.8, 0.6).normalize();
+    let diffuse = normal.dot(light).max(0.0);
+    let rim = (1.0 + normal.dot(direction)).powi(3);
+    vec3(0.65, 0.85, 1.0) * (0.15 + 0.85 * diffuse) + 0.2 * rim
+}
+
+/// Reference image of what the preview shader draws, `w` by `h` linear colors row by row from
+/// the top
+#[cfg(feature = "std")]
+pub fn render<#[cfg(feature = "rayon")] S: Sdf3 + Sync, #[cfg(not(feature = "rayon"))] S: Sdf3>(
+    sdf: &S,
+    camera: &Camera,
+    w: usize,
+    h: usize,
+) -> Vec<Vec3> {
+    #[cfg(feature = "rayon")]
+    use rayon::prelude::*;
+
+    let mut image = vec![Vec3::ZERO; w * h];
+
+    #[cfg(feature = "rayon")]
+    let iter = image.par_iter_mut();
+    #[cfg(not(feature = "rayon"))]
+    let iter = image.iter_mut();
+
+    iter.enumerate().for_each(|(i, color)| {
+        let y = i / w;
+        let x = i - y * w;
+        // pixel centers, mapped like the fragment coordinates of the shaders
+        let uv = (vec2(x as f32 + 0.5, -(y as f32 + 0.5)) - 0.5 * vec2(w as f32, -(h as f32)))
+            / h as f32;
+        *color = shade(sdf, camera, uv);
+    });
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::Disk;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn sdf3() {
+        let extruded = Extrude::new(Disk::new(0.1), 0.2, 0.0);
+        assert_approx_eq!(extruded.signed_distance(vec3(0.0, 0.0, 0.3)), 0.2);
+        assert_approx_eq!(extruded.signed_distance(vec3(0.3, 0.0, 0.0)), 0.2);
+        assert_approx_eq!(extruded.signed_distance(Vec3::ZERO), -0.1);
+        let rounded = Extrude::new(Disk::new(0.1), 0.2, 0.05);
+        assert_approx_eq!(rounded.signed_distance(vec3(0.0, 0.0, 0.3)), 0.2);
+        assert!(rounded.signed_distance(vec3(0.1, 0.0, 0.1)) > 0.0);
+
+        // a torus around the Y axis
+        let revolved = Revolve::new(Disk::new(0.1), 0.3);
+        assert_approx_eq!(revolved.signed_distance(vec3(0.0, 0.0, 0.3)), -0.1);
+        assert_approx_eq!(revolved.signed_distance(Vec3::ZERO), 0.2);
+
+        let t = raymarch(&extruded, vec3(0.0, 0.0, 1.0), -Vec3::Z, 10.0);
+        assert_approx_eq!(t.unwrap(), 0.9, 1e-3);
+        assert!(raymarch(&extruded, vec3(0.0, 0.5, 1.0), -Vec3::Z, 10.0).is_none());
+
+        let camera = Camera::default();
+        let image = render(&extruded, &camera, 32, 32);
+        assert_ne!(image[16 * 32 + 16], BACKGROUND);
+        assert_eq!(image[0], BACKGROUND);
+        assert_eq!(image[31 * 32 + 31], BACKGROUND);
+    }
+}
@@ -0,0 +1,272 @@
+//! Grids keeping full values only near the contour, for large fields which are only sampled
+//! within a band of it, like the glyphs and shapes of texture atlases
+
+use crate::{
+    grid::{Grid, Tile},
+    gridref::{pixel_at, pixel_center, view_bounds},
+    interval::{Aabb, Prune},
+    sdf::{Sdf, SignedDistance},
+};
+use glam::Vec2;
+
+/// Side in pixels of the blocks of a [SparseGrid]
+pub const BLOCK_SIZE: usize = 8;
+
+enum Block<T> {
+    /// Beyond the band everywhere, every pixel holding this value with its distance clamped to
+    /// the band
+    Far(T),
+    /// Values of the pixels row by row, the blocks on the right and bottom edges repeating the
+    /// last pixels of the grid
+    Near(Box<[T]>),
+}
+
+impl<T: SignedDistance> Block<T> {
+    /// Far if every value is beyond `band` on the same side of the contour
+    fn new(values: Vec<T>, band: f32) -> Self {
+        if values.iter().all(|v| v.value() > band) || values.iter().all(|v| v.value() < -band) {
+            Block::Far(clamp(values[0], band))
+        } else {
+            Block::Near(values.into_boxed_slice())
+        }
+    }
+}
+
+fn clamp<T: SignedDistance>(d: T, band: f32) -> T {
+    d.with_new_distance(d.value().clamp(-band, band))
+}
+
+/// Repeat the last column and row of the `n_x` by `n_y` pixels of a block over the rest of it
+fn pad<T: Copy>(values: &mut [T], n_x: usize, n_y: usize) {
+    for row in values.chunks_mut(BLOCK_SIZE).take(n_y) {
+        let last = row[n_x - 1];
+        row[n_x..].fill(last);
+    }
+    let (filled, rest) = values.split_at_mut(n_y * BLOCK_SIZE);
+    let last = &filled[(n_y - 1) * BLOCK_SIZE..];
+    for row in rest.chunks_mut(BLOCK_SIZE) {
+        row.copy_from_slice(last);
+    }
+}
+
+/// A grid split in blocks of [BLOCK_SIZE] pixels, storing a single clamped value for the blocks
+/// which are beyond a band around the contour
+pub struct SparseGrid<T> {
+    pub w: usize,
+    pub h: usize,
+    /// Area covered by the pixels, as [Grid::bounds]
+    pub bounds: Aabb,
+    /// Distance from the contour beyond which values are clamped
+    pub band: f32,
+    blocks: Vec<Block<T>>,
+}
+
+impl<
+        #[cfg(feature = "rayon")] T: SignedDistance + Send,
+        #[cfg(not(feature = "rayon"))] T: SignedDistance,
+    > SparseGrid<T>
+{
+    /// Compress `grid`, keeping the values of the blocks which come within `band` of the
+    /// contour
+    pub fn from_grid(grid: &Grid<T>, band: f32) -> Self {
+        let (w, h, bounds) = (grid.w, grid.h, grid.bounds);
+        let blocks = Self::block_tiles(w, h)
+            .map(|tile| {
+                let mut values = Vec::with_capacity(BLOCK_SIZE * BLOCK_SIZE);
+                for y in tile.y0..tile.y0 + BLOCK_SIZE {
+                    for x in tile.x0..tile.x0 + BLOCK_SIZE {
+                        values.push(grid.buffer[y.min(h - 1) * w + x.min(w - 1)]);
+                    }
+                }
+                Block::new(values, band)
+            })
+            .collect();
+        Self {
+            w,
+            h,
+            bounds,
+            band,
+            blocks,
+        }
+    }
+
+    /// Rasterize `sdf` like [Grid::rasterize], only evaluating the pixels of the blocks which
+    /// may come within `band` of the contour
+    pub fn from_sdf<
+        #[cfg(feature = "rayon")] S: Prune<T = T> + Sync,
+        #[cfg(not(feature = "rayon"))] S: Prune<T = T>,
+    >(
+        w: usize,
+        h: usize,
+        sdf: &S,
+        band: f32,
+    ) -> Self {
+        #[cfg(feature = "rayon")]
+        use rayon::prelude::*;
+
+        let bounds = view_bounds(w, h);
+        let tiles: Vec<Tile> = Self::block_tiles(w, h).collect();
+        #[cfg(feature = "rayon")]
+        let tiles = tiles.into_par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let tiles = tiles.into_iter();
+
+        let blocks = tiles
+            .map(|tile| {
+                let (pruned, interval) = sdf.prune(tile.aabb(bounds, w, h));
+                if interval.is_beyond(band) {
+                    let d = pruned.signed_distance(tile.aabb(bounds, w, h).center());
+                    return Block::Far(clamp(d, band));
+                }
+                let (n_x, n_y) = (tile.x1 - tile.x0, tile.y1 - tile.y0);
+                let mut values = vec![T::divergent(); BLOCK_SIZE * BLOCK_SIZE];
+                let mut points = [Vec2::ZERO; BLOCK_SIZE];
+                for (y, row) in (tile.y0..tile.y1).zip(values.chunks_mut(BLOCK_SIZE)) {
+                    for (x, p) in (tile.x0..tile.x1).zip(&mut points) {
+                        *p = pixel_center(bounds, w, h, x, y);
+                    }
+                    pruned.signed_distance_many(&points[..n_x], &mut row[..n_x]);
+                }
+                pad(&mut values, n_x, n_y);
+                Block::new(values, band)
+            })
+            .collect();
+        Self {
+            w,
+            h,
+            bounds,
+            band,
+            blocks,
+        }
+    }
+
+    /// The pixels of each block, clipped to the grid, row by row
+    fn block_tiles(w: usize, h: usize) -> impl Iterator<Item = Tile> {
+        (0..h.div_ceil(BLOCK_SIZE)).flat_map(move |by| {
+            (0..w.div_ceil(BLOCK_SIZE)).map(move |bx| {
+                let (x0, y0) = (bx * BLOCK_SIZE, by * BLOCK_SIZE);
+                Tile {
+                    x0,
+                    y0,
+                    x1: (x0 + BLOCK_SIZE).min(w),
+                    y1: (y0 + BLOCK_SIZE).min(h),
+                }
+            })
+        })
+    }
+}
+
+impl<T: SignedDistance> SparseGrid<T> {
+    pub fn aspect_ratio(&self) -> f32 {
+        self.w as f32 / self.h as f32
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> T {
+        let blocks_w = self.w.div_ceil(BLOCK_SIZE);
+        match &self.blocks[y / BLOCK_SIZE * blocks_w + x / BLOCK_SIZE] {
+            Block::Far(value) => *value,
+            Block::Near(values) => values[y % BLOCK_SIZE * BLOCK_SIZE + x % BLOCK_SIZE],
+        }
+    }
+
+    /// Value of the pixel containing `p`, like
+    /// [GridRef::signed_distance](crate::gridref::GridRef::signed_distance)
+    pub fn signed_distance(&self, p: Vec2) -> T {
+        let (x, y) = pixel_at(self.bounds, self.w, self.h, p);
+        self.get(x, y)
+    }
+
+    /// Number of blocks whose pixels are all stored
+    pub fn near_blocks(&self) -> usize {
+        self.blocks
+            .iter()
+            .filter(|block| matches!(block, Block::Near(_)))
+            .count()
+    }
+
+    pub fn to_grid(&self) -> Grid<T> {
+        let buffer = (0..self.w * self.h)
+            .map(|i| self.get(i % self.w, i / self.w))
+            .collect();
+        Grid {
+            w: self.w,
+            h: self.h,
+            bounds: self.bounds,
+            buffer,
+        }
+    }
+
+    /// Distances row by row, quantized by [quantize_i8]
+    pub fn to_i8(&self) -> Vec<i8> {
+        self.quantized(quantize_i8)
+    }
+
+    /// Distances row by row, quantized by [quantize_u8]
+    pub fn to_u8(&self) -> Vec<u8> {
+        self.quantized(quantize_u8)
+    }
+
+    fn quantized<Q>(&self, quantize: impl Fn(f32, f32) -> Q) -> Vec<Q> {
+        (0..self.w * self.h)
+            .map(|i| quantize(self.get(i % self.w, i / self.w).value(), self.band))
+            .collect()
+    }
+}
+
+/// Map `d` from [-band, band] to [-127, 127], negative inside like the distances
+pub fn quantize_i8(d: f32, band: f32) -> i8 {
+    (d / band * 127.0).round().clamp(-127.0, 127.0) as i8
+}
+
+pub fn dequantize_i8(q: i8, band: f32) -> f32 {
+    q as f32 / 127.0 * band
+}
+
+/// Map `d` from [-band, band] to [0, 255], the contour falling between 127 and 128
+pub fn quantize_u8(d: f32, band: f32) -> u8 {
+    ((d / band + 1.0) * 127.5).round().clamp(0.0, 255.0) as u8
+}
+
+pub fn dequantize_u8(q: u8, band: f32) -> f32 {
+    (q as f32 / 127.5 - 1.0) * band
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{primitives::Torus, primitives_enum::Shape};
+    use assert_approx_eq::assert_approx_eq;
+    use glam::vec2;
+
+    #[test]
+    fn sparse_grid() {
+        const W: usize = 250;
+        const H: usize = 200;
+        const BAND: f32 = 0.05;
+
+        let torus = Shape::Torus(Torus::new(0.3, 0.02));
+        let reference = Grid::from_sdf(W, H, &torus);
+        let sparse = SparseGrid::from_sdf(W, H, &torus, BAND);
+        assert!(sparse.near_blocks() < W.div_ceil(BLOCK_SIZE) * H.div_ceil(BLOCK_SIZE) / 2);
+        for (d, expected) in sparse.to_grid().buffer.iter().zip(&reference.buffer) {
+            if expected.abs() <= BAND {
+                assert_eq!(d, expected);
+            } else {
+                assert_eq!(d.signum(), expected.signum());
+                assert!(d.abs() >= BAND && d.abs() <= expected.abs());
+            }
+        }
+        let compressed = SparseGrid::from_grid(&reference, BAND);
+        assert_eq!(compressed.near_blocks(), sparse.near_blocks());
+        let d = compressed.signed_distance(vec2(0.3, 0.0));
+        assert_approx_eq!(d, -0.02, 1.0 / H as f32);
+
+        for (q, d) in sparse.to_i8().into_iter().zip(&reference.buffer) {
+            assert_approx_eq!(dequantize_i8(q, BAND), d.clamp(-BAND, BAND), BAND / 127.0);
+        }
+        for (q, d) in sparse.to_u8().into_iter().zip(&reference.buffer) {
+            assert_approx_eq!(dequantize_u8(q, BAND), d.clamp(-BAND, BAND), BAND / 127.0);
+            assert_eq!(q >= 128, *d > 0.0);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Strategies and helpers shared by the tests of the modules
+
+use crate::{
+    primitives::*,
+    primitives_enum::Shape,
+    sdf::{Sdf, SignedDistance},
+};
+use glam::{vec2, Vec2};
+use proptest::prelude::*;
+
+pub const EPSILON: f32 = 1e-4;
+
+pub fn point() -> impl Strategy<Value = Vec2> {
+    (-1.0f32..1.0, -1.0f32..1.0).prop_map(|(x, y)| vec2(x, y))
+}
+
+pub fn direction() -> impl Strategy<Value = Vec2> {
+    (0.0f32..core::f32::consts::TAU).prop_map(Vec2::from_angle)
+}
+
+pub fn shape() -> impl Strategy<Value = Shape> {
+    let size = || 0.01f32..0.5;
+    prop_oneof![
+        size().prop_map(|r| Disk::new(r).into()),
+        (size(), size()).prop_map(|(major, minor)| Torus::new(major, minor).into()),
+        (size(), size()).prop_map(|(w, h)| Rectangle::new(w, h).into()),
+        (size(), size()).prop_map(|(a, b)| Cross::new(a.max(b), a.min(b)).into()),
+        direction().prop_map(|n| Plane::new(n).into()),
+        direction().prop_map(|d| Ray::new(d).into()),
+        (point(), point()).prop_map(|(a, b)| LineSegment::new(a, b).into()),
+        proptest::collection::vec((point(), 0.01f32..0.2), 1..6).prop_map(|points| {
+            let (points, radii): (Vec<_>, Vec<_>) = points.into_iter().unzip();
+            CapsuleChain::new(&points, &radii).into()
+        }),
+    ]
+}
+
+/// Whether `p` is inside `shape`, independently of its sdf
+pub fn contains(shape: &Shape, p: Vec2) -> bool {
+    match shape {
+        Shape::Disk(disk) => p.length() < disk.radius,
+        Shape::Torus(torus) => (p.length() - torus.major_radius).abs() < torus.minor_radius,
+        Shape::Rectangle(rectangle) => {
+            p.x.abs() < 0.5 * rectangle.width && p.y.abs() < 0.5 * rectangle.height
+        }
+        Shape::Cross(cross) => {
+            let p = p.abs();
+            (p.x < cross.length && p.y < cross.thickness)
+                || (p.y < cross.length && p.x < cross.thickness)
+        }
+        Shape::Plane(plane) => plane.normal.dot(p) < 0.0,
+        Shape::Ray(_) | Shape::LineSegment(_) => false,
+        Shape::CapsuleChain(chain) => {
+            let (points, radii) = (chain.points(), chain.radii());
+            let segment = |i: usize| {
+                // the distance to the disks along the segment is convex in t
+                let f = |t: f32| {
+                    p.distance(points[i].lerp(points[i + 1], t))
+                        - (radii[i] + t * (radii[i + 1] - radii[i]))
+                };
+                let (mut lo, mut hi) = (0.0, 1.0);
+                for _ in 0..100 {
+                    let (a, b) = (lo + (hi - lo) / 3.0, hi - (hi - lo) / 3.0);
+                    if f(a) < f(b) {
+                        hi = b;
+                    } else {
+                        lo = a;
+                    }
+                }
+                f(0.5 * (lo + hi)) < 0.0
+            };
+            p.distance(points[0]) < radii[0] || (0..points.len() - 1).any(segment)
+        }
+    }
+}
+
+/// Central difference gradient, if the sdf is smooth enough around `p` for it to mean
+/// anything
+pub fn gradient<S: Sdf>(sdf: &S, p: Vec2) -> Option<Vec2> {
+    const H: f32 = 1e-3;
+    let d = |p: Vec2| sdf.signed_distance(p).value();
+    let forward = vec2(d(p + H * Vec2::X), d(p + H * Vec2::Y)) - d(p);
+    let backward = d(p) - vec2(d(p - H * Vec2::X), d(p - H * Vec2::Y));
+    ((forward - backward).abs().max_element() < 0.01 * H).then(|| (forward + backward) / (2.0 * H))
+}
+
+/// Check that the distances of `sdf` change no faster than the position, between `p` and `q`
+/// and around `p`
+pub fn check_bounds<S: Sdf>(sdf: &S, p: Vec2, q: Vec2) -> Result<(), TestCaseError> {
+    let (dp, dq) = (
+        sdf.signed_distance(p).value(),
+        sdf.signed_distance(q).value(),
+    );
+    prop_assert!(
+        (dp - dq).abs() <= p.distance(q) + EPSILON,
+        "not 1-Lipschitz"
+    );
+    if let Some(g) = gradient(sdf, p) {
+        prop_assert!(g.length() <= 1.0 + 1e-2, "gradient too steep: {g}");
+    }
+    Ok(())
+}
+
+/// Sdf from a closure, for composing shapes in tests
+pub struct FnSdf<F>(pub F);
+
+impl<F: Fn(Vec2) -> f32> Sdf for FnSdf<F> {
+    type T = f32;
+    fn signed_distance(&self, p: Vec2) -> f32 {
+        (self.0)(p)
+    }
+}
@@ -0,0 +1,450 @@
+//! Python bindings to build scenes and evaluate them with the same interpreter as the runner
+//!
+//! ```python
+//! import numpy as np
+//! from sdf_builder import Node, Scene
+//!
+//! scene = (
+//!     Scene()
+//!     .union([Node.shape("Disk", radius=0.2), Node.shape("Rectangle", x=0.2, width=0.4)])
+//!     .subtract([Node.shape("Disk", radius=0.05)])
+//! )
+//! distances, ids = scene.evaluate(np.array([[0.0, 0.0], [0.3, 0.1]], dtype=np.float32))
+//! distances, ids = scene.bake(512, 512)
+//! ```
+
+use dfutils::{grid::Grid, primitives_enum::Shape, sdf::Sdf};
+use glam::{vec2, Vec2};
+use numpy::{Ix1, Ix2, PyArray, PyArray1, PyArrayMethods, PyReadonlyArray2};
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyDict, PyType},
+};
+use rayon::prelude::*;
+use sdf_scene::{parameters::Parameters, ItemId};
+use shared::{
+    sdf_interpreter::{Modifier, Operator, SdfInstructions, Transform},
+    sdf_wrapper::WrappedDistance,
+};
+use std::{collections::HashMap, path::PathBuf};
+use strum::IntoEnumIterator;
+
+/// Arrays of distances and of the ids of the closest items, of the same shape
+type DistancesAndIds<'py, D> = (Bound<'py, PyArray<f32, D>>, Bound<'py, PyArray<u32, D>>);
+
+/// Find the variant of `T` named `kind`
+fn variant<T: IntoEnumIterator + Into<&'static str> + Copy>(kind: &str) -> PyResult<T> {
+    T::iter().find(|v| (*v).into() == kind).ok_or_else(|| {
+        let names: Vec<&str> = T::iter().map(Into::into).collect();
+        PyValueError::new_err(format!(
+            "unknown kind '{kind}', expected one of {}",
+            names.join(", ")
+        ))
+    })
+}
+
+/// Set the parameters of `target` from keyword arguments, where `_` can stand for the `.` of
+/// names like `a.x`
+fn set_parameters<T: Parameters + Copy>(
+    mut target: T,
+    params: Option<&Bound<'_, PyDict>>,
+) -> PyResult<T> {
+    let Some(params) = params else {
+        return Ok(target);
+    };
+    let names: Vec<&str> = target.parameters().iter().map(|(name, _)| *name).collect();
+    for (key, value) in params.extract::<HashMap<String, f32>>()? {
+        let dotted = key.replace('_', ".");
+        let Some(name) = names.iter().find(|n| **n == key || **n == dotted) else {
+            return Err(PyValueError::new_err(format!(
+                "unknown parameter '{key}', expected one of {}",
+                names.join(", ")
+            )));
+        };
+        target = target.set_parameter(name, value);
+    }
+    Ok(target)
+}
+
+/// Placement from a position and a counterclockwise rotation in degrees
+fn transform(x: f32, y: f32, rotation: f32) -> Transform {
+    Transform {
+        position: vec2(x, y),
+        rotation: rotation.to_radians(),
+    }
+}
+
+/// A subtree of a scene: a shape, an operator over other nodes, a lerp or a modifier
+#[pyclass(name = "Node", module = "sdf_builder", frozen)]
+#[derive(Clone)]
+struct PyNode(sdf_scene::Node);
+
+fn nodes(nodes: Vec<PyRef<'_, PyNode>>) -> Vec<sdf_scene::Node> {
+    nodes.iter().map(|node| node.0.clone()).collect()
+}
+
+#[pymethods]
+impl PyNode {
+    /// A primitive by name, e.g. `Node.shape("Disk", radius=0.1, x=0.2)`, its other parameters
+    /// keeping their defaults
+    #[classmethod]
+    #[pyo3(signature = (kind, x=0.0, y=0.0, rotation=0.0, **params))]
+    fn shape(
+        _cls: &Bound<'_, PyType>,
+        kind: &str,
+        x: f32,
+        y: f32,
+        rotation: f32,
+        params: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Self> {
+        let shape = set_parameters(variant::<Shape>(kind)?, params)?;
+        Ok(Self(sdf_scene::Node::Shape(
+            shape,
+            transform(x, y, rotation),
+        )))
+    }
+
+    /// One of `Union`, `Intersect`, `Subtract` or `Xor` over `children`
+    #[classmethod]
+    fn operator(
+        _cls: &Bound<'_, PyType>,
+        kind: &str,
+        children: Vec<PyRef<'_, PyNode>>,
+    ) -> PyResult<Self> {
+        let op = variant::<Operator>(kind)?;
+        Ok(Self(sdf_scene::Node::operator(op, nodes(children))))
+    }
+
+    #[classmethod]
+    fn union(_cls: &Bound<'_, PyType>, children: Vec<PyRef<'_, PyNode>>) -> Self {
+        Self(sdf_scene::Node::union(nodes(children)))
+    }
+
+    #[classmethod]
+    fn intersect(_cls: &Bound<'_, PyType>, children: Vec<PyRef<'_, PyNode>>) -> Self {
+        Self(sdf_scene::Node::intersect(nodes(children)))
+    }
+
+    /// The first child minus all the others
+    #[classmethod]
+    fn subtract(_cls: &Bound<'_, PyType>, children: Vec<PyRef<'_, PyNode>>) -> Self {
+        Self(sdf_scene::Node::subtract(nodes(children)))
+    }
+
+    #[classmethod]
+    fn xor(_cls: &Bound<'_, PyType>, children: Vec<PyRef<'_, PyNode>>) -> Self {
+        Self(sdf_scene::Node::xor(nodes(children)))
+    }
+
+    /// Interpolation between the distances of `a` and `b` by `t` in [0, 1]
+    #[classmethod]
+    fn lerp(_cls: &Bound<'_, PyType>, t: f32, a: PyRef<'_, PyNode>, b: PyRef<'_, PyNode>) -> Self {
+        Self(sdf_scene::Node::lerp(t, a.0.clone(), b.0.clone()))
+    }
+
+    /// A modifier by name, e.g. `Node.modify("Outline", child, width=0.01)`
+    #[classmethod]
+    #[pyo3(signature = (kind, child, **params))]
+    fn modify(
+        _cls: &Bound<'_, PyType>,
+        kind: &str,
+        child: PyRef<'_, PyNode>,
+        params: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Self> {
+        let modifier = set_parameters(variant::<Modifier>(kind)?, params)?;
+        Ok(Self(sdf_scene::Node::modify(modifier, child.0.clone())))
+    }
+
+    /// The same subtree moved by `(x, y)` and rotated by `rotation` degrees around the origin
+    #[pyo3(signature = (x=0.0, y=0.0, rotation=0.0))]
+    fn transformed(&self, x: f32, y: f32, rotation: f32) -> Self {
+        Self(self.0.clone().transformed(transform(x, y, rotation)))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+/// A composition, built from nodes or loaded from a scene file of the runner
+#[pyclass(name = "Scene", module = "sdf_builder")]
+struct PyScene(sdf_scene::Scene);
+
+impl PyScene {
+    /// Combine each of `nodes` with the top level by `op`
+    fn combine<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        op: Operator,
+        nodes: Vec<PyRef<'py, PyNode>>,
+    ) -> PyRefMut<'py, Self> {
+        for node in nodes {
+            slf.0.combine(op, node.0.clone());
+        }
+        slf
+    }
+}
+
+#[pymethods]
+impl PyScene {
+    #[new]
+    fn new() -> Self {
+        Self(sdf_scene::Scene::new())
+    }
+
+    #[staticmethod]
+    fn load(path: PathBuf) -> PyResult<Self> {
+        Ok(Self(sdf_scene::Scene::load(&path)?))
+    }
+
+    fn save(&self, path: PathBuf) -> PyResult<()> {
+        Ok(self.0.save(&path)?)
+    }
+
+    /// Add `nodes` to the top level, returns the scene for chaining
+    fn union<'py>(slf: PyRefMut<'py, Self>, nodes: Vec<PyRef<'py, PyNode>>) -> PyRefMut<'py, Self> {
+        Self::combine(slf, Operator::Union, nodes)
+    }
+
+    /// Intersect the scene so far with each of `nodes`
+    fn intersect<'py>(
+        slf: PyRefMut<'py, Self>,
+        nodes: Vec<PyRef<'py, PyNode>>,
+    ) -> PyRefMut<'py, Self> {
+        Self::combine(slf, Operator::Intersect, nodes)
+    }
+
+    /// Subtract each of `nodes` from the scene so far
+    fn subtract<'py>(
+        slf: PyRefMut<'py, Self>,
+        nodes: Vec<PyRef<'py, PyNode>>,
+    ) -> PyRefMut<'py, Self> {
+        Self::combine(slf, Operator::Subtract, nodes)
+    }
+
+    fn xor<'py>(slf: PyRefMut<'py, Self>, nodes: Vec<PyRef<'py, PyNode>>) -> PyRefMut<'py, Self> {
+        Self::combine(slf, Operator::Xor, nodes)
+    }
+
+    /// Combine `node` with the top level by `operator`, returns the id of its top item, as
+    /// reported by `evaluate` and `bake` for its shapes
+    #[pyo3(signature = (node, operator="Union"))]
+    fn add(&mut self, node: PyRef<'_, PyNode>, operator: &str) -> PyResult<u32> {
+        let op = variant::<Operator>(operator)?;
+        Ok(self.0.combine(op, node.0.clone()).0)
+    }
+
+    /// Add a variable, usable in the expressions of bindings and of later variables
+    fn variable<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        name: &str,
+        expression: &str,
+    ) -> PyRefMut<'py, Self> {
+        slf.0 = std::mem::take(&mut slf.0).variable(name, expression);
+        slf
+    }
+
+    /// Position on the timeline in seconds, effective after `apply_parameters`
+    #[getter]
+    fn time(&self) -> f32 {
+        self.0.timeline.time
+    }
+
+    #[setter]
+    fn set_time(&mut self, time: f32) {
+        self.0.timeline.time = time;
+    }
+
+    /// Write the values of bound and animated parameters into the items, returns the errors of
+    /// the evaluation of the expressions
+    fn apply_parameters(&mut self) -> Vec<String> {
+        self.0.apply_parameters();
+        self.0.parameter_errors().to_vec()
+    }
+
+    /// Name of an item, or its type if it has none
+    fn label(&self, id: u32) -> String {
+        self.0.label(ItemId(id))
+    }
+
+    /// Signed distance and id of the closest item at a point
+    fn signed_distance(&self, x: f32, y: f32) -> (f32, u32) {
+        let instructions = self.0.generate_instructions();
+        let d = SdfInstructions::new(&instructions).signed_distance(vec2(x, y));
+        (d.d, d.data.0)
+    }
+
+    /// Signed distances and item ids at `points`, an array of shape (n, 2)
+    fn evaluate<'py>(
+        &self,
+        py: Python<'py>,
+        points: PyReadonlyArray2<'py, f32>,
+    ) -> PyResult<DistancesAndIds<'py, Ix1>> {
+        let points = points.as_array();
+        if points.ncols() != 2 {
+            return Err(PyValueError::new_err("points must have shape (n, 2)"));
+        }
+        let points: Vec<Vec2> = points
+            .rows()
+            .into_iter()
+            .map(|p| vec2(p[0], p[1]))
+            .collect();
+        let instructions = self.0.generate_instructions();
+        let mut values = vec![WrappedDistance::<ItemId>::default(); points.len()];
+        py.detach(|| {
+            let sdf = SdfInstructions::new(&instructions);
+            points
+                .par_chunks(1024)
+                .zip(values.par_chunks_mut(1024))
+                .for_each(|(points, values)| sdf.signed_distance_many(points, values));
+        });
+        let (distances, ids) = split(&values);
+        Ok((
+            PyArray1::from_vec(py, distances),
+            PyArray1::from_vec(py, ids),
+        ))
+    }
+
+    /// Rasterize into `height` rows of `width` pixels from the top, covering the view of the
+    /// runner: y from -0.5 to 0.5 and x over the same scale. Returns the signed distances and the
+    /// item ids, made exact Euclidean distances by a distance transform if `exact`.
+    ///
+    /// With a `band`, distances are only computed within it of the contours, the rest holding
+    /// lower bounds, which is much faster for large sizes.
+    #[pyo3(signature = (width, height, exact=false, band=None))]
+    fn bake<'py>(
+        &self,
+        py: Python<'py>,
+        width: usize,
+        height: usize,
+        exact: bool,
+        band: Option<f32>,
+    ) -> PyResult<DistancesAndIds<'py, Ix2>> {
+        let instructions = self.0.generate_instructions();
+        let grid = py.detach(|| {
+            let sdf = SdfInstructions::new(&instructions);
+            let mut grid = Grid::new(width, height);
+            match band {
+                Some(band) => grid.rasterize(&sdf, band),
+                None => grid.update(&sdf),
+            }
+            if exact {
+                grid.exactify();
+            }
+            grid
+        });
+        let (distances, ids) = split(&grid.buffer);
+        Ok((
+            PyArray1::from_vec(py, distances).reshape([height, width])?,
+            PyArray1::from_vec(py, ids).reshape([height, width])?,
+        ))
+    }
+}
+
+fn split(values: &[WrappedDistance<ItemId>]) -> (Vec<f32>, Vec<u32>) {
+    values.iter().map(|v| (v.d, v.data.0)).unzip()
+}
+
+#[pymodule]
+fn sdf_builder(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyNode>()?;
+    m.add_class::<PyScene>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dfutils::primitives::{Disk, LineSegment};
+    use numpy::{PyArray2, PyUntypedArrayMethods};
+
+    fn python<R>(f: impl for<'py> FnOnce(Python<'py>) -> R) -> R {
+        Python::initialize();
+        Python::attach(f)
+    }
+
+    fn disk_scene() -> PyScene {
+        let mut scene = sdf_scene::Scene::new();
+        scene.combine(
+            Operator::Union,
+            sdf_scene::Node::Shape(Disk::new(0.2).into(), Transform::default()),
+        );
+        PyScene(scene)
+    }
+
+    #[test]
+    fn parameters() {
+        python(|py| {
+            // underscores stand for dots, and names with dots are accepted as is
+            let params = PyDict::new(py);
+            params.set_item("a_x", 0.3).unwrap();
+            params.set_item("b.y", -0.2).unwrap();
+            let shape = set_parameters(Shape::LineSegment(LineSegment::default()), Some(&params));
+            let Ok(Shape::LineSegment(segment)) = shape else {
+                panic!("not a line segment");
+            };
+            assert_eq!((segment.a.x, segment.b.y), (0.3, -0.2));
+
+            let params = PyDict::new(py);
+            params.set_item("width", 1.0).unwrap();
+            let error = set_parameters(Shape::Disk(Disk::default()), Some(&params)).unwrap_err();
+            assert!(error
+                .to_string()
+                .contains("unknown parameter 'width', expected one of radius"));
+
+            assert!(variant::<Shape>("Disk").is_ok());
+            assert!(variant::<Shape>("Circle").is_err());
+        });
+    }
+
+    #[test]
+    fn evaluate() {
+        python(|py| {
+            let scene = disk_scene();
+            let points = vec![vec![0.0, 0.0], vec![0.5, 0.0], vec![0.0, -0.3]];
+            let points = PyArray2::from_vec2(py, &points).unwrap();
+            let (distances, ids) = scene.evaluate(py, points.readonly()).unwrap();
+            assert_eq!(distances.shape(), [3]);
+            assert_eq!(ids.shape(), [3]);
+            let distances = distances.to_vec().unwrap();
+            assert!((distances[0] + 0.2).abs() < 1e-6);
+            assert!((distances[1] - 0.3).abs() < 1e-6);
+
+            let points = PyArray2::from_vec2(py, &[vec![0.0, 0.0, 0.0]]).unwrap();
+            let error = scene.evaluate(py, points.readonly()).unwrap_err();
+            assert!(error.to_string().contains("points must have shape (n, 2)"));
+        });
+    }
+
+    #[test]
+    fn bake() {
+        python(|py| {
+            let scene = disk_scene();
+            let (w, h) = (48, 32);
+            let (distances, ids) = scene.bake(py, w, h, false, None).unwrap();
+            assert_eq!(distances.shape(), [h, w]);
+            assert_eq!(ids.shape(), [h, w]);
+            let distances = distances.to_vec().unwrap();
+
+            // the field of a disk is already exact, so it is only moved by the contour search
+            let pixel = Grid::<f32>::new(w, h).pixel_size().max_element();
+            let (exact, _) = scene.bake(py, w, h, true, None).unwrap();
+            assert_eq!(exact.shape(), [h, w]);
+            for (d, e) in distances.iter().zip(exact.to_vec().unwrap()) {
+                assert!((d - e).abs() < 0.5 * pixel, "{d} made {e}");
+            }
+
+            // within the band distances are unchanged, beyond it they are lower bounds
+            let band = 0.05;
+            let (banded, _) = scene.bake(py, w, h, false, Some(band)).unwrap();
+            assert_eq!(banded.shape(), [h, w]);
+            for (d, b) in distances.iter().zip(banded.to_vec().unwrap()) {
+                if d.abs() < band {
+                    assert!((d - b).abs() < 1e-6);
+                } else {
+                    assert!(b.signum() == d.signum() && b.abs() <= d.abs() + 1e-6);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,280 @@
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoStaticStr};
+
+/// How a track moves from a key to the next one
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, EnumIter, IntoStaticStr,
+)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// Smoothstep, slow at both keys
+    Ease,
+    /// Hold the value until the next key
+    Step,
+}
+
+impl Interpolation {
+    fn apply(&self, a: f32, b: f32, t: f32) -> f32 {
+        let t = match self {
+            Interpolation::Linear => t,
+            Interpolation::Ease => t * t * (3.0 - 2.0 * t),
+            Interpolation::Step => 0.0,
+        };
+        a + (b - a) * t
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub time: f32,
+    pub value: f32,
+    /// Interpolation towards the next key
+    pub interpolation: Interpolation,
+}
+
+/// Keyframes of a single parameter, sorted by time and at least [Track::SAME_TIME] apart
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Keys")]
+pub struct Track {
+    keys: Vec<Keyframe>,
+}
+
+/// Keys as saved, which may be unsorted or share times when edited by hand
+#[derive(Deserialize)]
+struct Keys {
+    keys: Vec<Keyframe>,
+}
+
+impl From<Keys> for Track {
+    fn from(keys: Keys) -> Self {
+        let mut track = Track::default();
+        for key in keys.keys {
+            track.set_key(key);
+        }
+        track
+    }
+}
+
+impl Track {
+    /// Keys closer in time than this are replaced by the last one set
+    pub const SAME_TIME: f32 = 1e-3;
+
+    pub fn keys(&self) -> &[Keyframe] {
+        &self.keys
+    }
+
+    /// Value at `time`, holding the first and last values outside of the keys
+    pub fn sample(&self, time: f32) -> Option<f32> {
+        let next = self.keys.partition_point(|key| key.time <= time);
+        match (
+            next.checked_sub(1).map(|i| self.keys[i]),
+            self.keys.get(next).copied(),
+        ) {
+            (Some(a), Some(b)) => {
+                let t = (time - a.time) / (b.time - a.time);
+                Some(a.interpolation.apply(a.value, b.value, t))
+            }
+            (Some(key), None) | (None, Some(key)) => Some(key.value),
+            (None, None) => None,
+        }
+    }
+
+    /// Add a key, replacing any key at the same time, unless its time is not finite
+    pub fn set_key(&mut self, key: Keyframe) {
+        if !key.time.is_finite() {
+            return;
+        }
+        self.keys
+            .retain(|k| (k.time - key.time).abs() > Self::SAME_TIME);
+        let index = self.keys.partition_point(|k| k.time < key.time);
+        self.keys.insert(index, key);
+    }
+}
+
+/// Playback state of the scene animation, in seconds
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Timeline {
+    pub time: f32,
+    pub duration: f32,
+    #[serde(skip)]
+    pub playing: bool,
+    pub looping: bool,
+    /// Frame rate of exported sequences
+    pub fps: u32,
+    /// Size in pixels of exported frames
+    pub export_size: (usize, usize),
+    /// Directory receiving exported frames
+    pub export_dir: String,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            duration: 2.0,
+            playing: false,
+            looping: true,
+            fps: 30,
+            export_size: (512, 512),
+            export_dir: "frames".to_string(),
+        }
+    }
+}
+
+impl Timeline {
+    /// Move the time forward while playing, returns whether it changed
+    pub fn advance(&mut self, dt: f32) -> bool {
+        if !self.playing {
+            return false;
+        }
+        self.time += dt;
+        if self.time > self.duration {
+            if self.looping && self.duration > 0.0 {
+                self.time %= self.duration;
+            } else {
+                self.time = self.duration;
+                self.playing = false;
+            }
+        }
+        true
+    }
+
+    pub fn frame_count(&self) -> usize {
+        (self.duration * self.fps as f32).ceil() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(time: f32, value: f32, interpolation: Interpolation) -> Keyframe {
+        Keyframe {
+            time,
+            value,
+            interpolation,
+        }
+    }
+
+    fn track(keys: &[Keyframe]) -> Track {
+        let mut track = Track::default();
+        for k in keys {
+            track.set_key(*k);
+        }
+        track
+    }
+
+    #[test]
+    fn interpolations() {
+        let sample = |interpolation, time| {
+            track(&[key(1.0, 2.0, interpolation), key(3.0, 6.0, interpolation)]).sample(time)
+        };
+        assert_eq!(sample(Interpolation::Linear, 2.0), Some(4.0));
+        assert_eq!(sample(Interpolation::Linear, 1.5), Some(3.0));
+        assert_eq!(sample(Interpolation::Ease, 2.0), Some(4.0));
+        // smoothstep of a quarter is 0.15625
+        assert_eq!(sample(Interpolation::Ease, 1.5), Some(2.625));
+        assert_eq!(sample(Interpolation::Step, 2.9), Some(2.0));
+        assert_eq!(sample(Interpolation::Step, 3.0), Some(6.0));
+
+        // each key sets the interpolation towards the next one
+        let mixed = track(&[
+            key(0.0, 0.0, Interpolation::Step),
+            key(1.0, 1.0, Interpolation::Linear),
+            key(2.0, 3.0, Interpolation::Step),
+        ]);
+        assert_eq!(mixed.sample(0.5), Some(0.0));
+        assert_eq!(mixed.sample(1.5), Some(2.0));
+    }
+
+    #[test]
+    fn clamping() {
+        let track = track(&[
+            key(1.0, 2.0, Interpolation::Linear),
+            key(3.0, 6.0, Interpolation::Linear),
+        ]);
+        assert_eq!(track.sample(-5.0), Some(2.0));
+        assert_eq!(track.sample(1.0), Some(2.0));
+        assert_eq!(track.sample(3.0), Some(6.0));
+        assert_eq!(track.sample(10.0), Some(6.0));
+        assert_eq!(Track::default().sample(1.0), None);
+        let single = self::track(&[key(1.0, 7.0, Interpolation::Ease)]);
+        assert_eq!(single.sample(0.0), Some(7.0));
+        assert_eq!(single.sample(2.0), Some(7.0));
+    }
+
+    #[test]
+    fn set_key() {
+        let mut track = track(&[
+            key(2.0, 1.0, Interpolation::Linear),
+            key(0.0, 0.0, Interpolation::Linear),
+            key(1.0, 5.0, Interpolation::Linear),
+        ]);
+        let times = |track: &Track| track.keys().iter().map(|k| k.time).collect::<Vec<_>>();
+        assert_eq!(times(&track), [0.0, 1.0, 2.0]);
+
+        // keys at the same time are replaced, whichever side they fall on
+        track.set_key(key(1.0 + 0.5 * Track::SAME_TIME, 7.0, Interpolation::Step));
+        track.set_key(key(
+            2.0 - 0.5 * Track::SAME_TIME,
+            8.0,
+            Interpolation::Linear,
+        ));
+        assert_eq!(times(&track), [0.0, 1.0005, 1.9995]);
+        assert_eq!(track.keys()[1].value, 7.0);
+        assert_eq!(track.keys()[1].interpolation, Interpolation::Step);
+        assert_eq!(track.sample(1.5), Some(7.0));
+
+        track.set_key(key(f32::NAN, 9.0, Interpolation::Linear));
+        assert_eq!(track.keys().len(), 3);
+    }
+
+    #[test]
+    fn load_unsorted_keys() {
+        let json = r#"{"keys": [
+            {"time": 2.0, "value": 4.0, "interpolation": "Linear"},
+            {"time": 0.0, "value": 0.0, "interpolation": "Linear"},
+            {"time": 2.0, "value": 6.0, "interpolation": "Linear"}
+        ]}"#;
+        let track: Track = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            track.keys(),
+            [
+                key(0.0, 0.0, Interpolation::Linear),
+                key(2.0, 6.0, Interpolation::Linear)
+            ]
+        );
+        assert_eq!(track.sample(1.0), Some(3.0));
+        let saved = serde_json::to_string(&track).unwrap();
+        assert_eq!(serde_json::from_str::<Track>(&saved).unwrap(), track);
+    }
+
+    #[test]
+    fn timeline() {
+        let mut timeline = Timeline {
+            duration: 2.0,
+            ..Default::default()
+        };
+        assert!(!timeline.advance(0.5));
+        assert_eq!(timeline.time, 0.0);
+
+        timeline.playing = true;
+        assert!(timeline.advance(1.5));
+        assert_eq!(timeline.time, 1.5);
+        assert!(timeline.advance(1.0));
+        assert_eq!(timeline.time, 0.5);
+        assert!(timeline.playing);
+
+        timeline.looping = false;
+        assert!(timeline.advance(2.0));
+        assert_eq!(timeline.time, 2.0);
+        assert!(!timeline.playing);
+        assert!(!timeline.advance(0.1));
+
+        assert_eq!(timeline.frame_count(), 60);
+        timeline.duration = 0.51;
+        assert_eq!(timeline.frame_count(), 16);
+    }
+}
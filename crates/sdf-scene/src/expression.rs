@@ -0,0 +1,325 @@
+//! Arithmetic expressions over named scene variables, e.g. `w/2 + margin`
+//!
+//! Supported: numbers, variables, `+ - * / ^`, parentheses, the constant `pi` and the functions
+//! `abs`, `sqrt`, `sin`, `cos`, `min` and `max`.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    Number(f32),
+    Variable(String),
+    Neg(Box<Expression>),
+    Binary(char, Box<Expression>, Box<Expression>),
+    Call(String, Vec<Expression>),
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            pos: 0,
+        };
+        let expression = parser.expression()?;
+        parser.skip_whitespace();
+        if let Some(c) = parser.peek() {
+            return Err(format!("unexpected '{c}' at {}", parser.pos));
+        }
+        Ok(expression)
+    }
+
+    /// Evaluate with the values of the variables given by `lookup`
+    pub fn eval(&self, lookup: &dyn Fn(&str) -> Option<f32>) -> Result<f32, String> {
+        Ok(match self {
+            Expression::Number(v) => *v,
+            Expression::Variable(name) if name == "pi" => std::f32::consts::PI,
+            Expression::Variable(name) => {
+                lookup(name).ok_or_else(|| format!("unknown variable '{name}'"))?
+            }
+            Expression::Neg(e) => -e.eval(lookup)?,
+            Expression::Binary(op, a, b) => {
+                let (a, b) = (a.eval(lookup)?, b.eval(lookup)?);
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    _ => a.powf(b),
+                }
+            }
+            Expression::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval(lookup))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match (name.as_str(), args.as_slice()) {
+                    ("abs", [x]) => x.abs(),
+                    ("sqrt", [x]) => x.sqrt(),
+                    ("sin", [x]) => x.sin(),
+                    ("cos", [x]) => x.cos(),
+                    ("min", [x, y]) => x.min(*y),
+                    ("max", [x, y]) => x.max(*y),
+                    _ => {
+                        return Err(format!(
+                            "unknown function '{name}' with {} arguments",
+                            args.len()
+                        ))
+                    }
+                }
+            }
+        })
+    }
+
+    /// Names of the variables used by the expression
+    pub fn variables(&self) -> Vec<&str> {
+        let mut variables = vec![];
+        self.collect_variables(&mut variables);
+        variables
+    }
+
+    fn collect_variables<'a>(&'a self, variables: &mut Vec<&'a str>) {
+        match self {
+            Expression::Number(_) => {}
+            Expression::Variable(name) => {
+                if name != "pi" && !variables.contains(&name.as_str()) {
+                    variables.push(name);
+                }
+            }
+            Expression::Neg(e) => e.collect_variables(variables),
+            Expression::Binary(_, a, b) => {
+                a.collect_variables(variables);
+                b.collect_variables(variables);
+            }
+            Expression::Call(_, args) => {
+                for arg in args {
+                    arg.collect_variables(variables);
+                }
+            }
+        }
+    }
+}
+
+/// Recursive descent parser, one method per precedence level
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume `c` if it is the next non whitespace character
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expression, String> {
+        let mut lhs = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                '+'
+            } else if self.eat('-') {
+                '-'
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expression::Binary(op, Box::new(lhs), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expression, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                '*'
+            } else if self.eat('/') {
+                '/'
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expression::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expression, String> {
+        if self.eat('-') {
+            Ok(Expression::Neg(Box::new(self.unary()?)))
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<Expression, String> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            Ok(Expression::Binary(
+                '^',
+                Box::new(base),
+                Box::new(self.unary()?),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expression, String> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let e = self.expression()?;
+                if !self.eat(')') {
+                    return Err(format!("missing ')' at {}", self.pos));
+                }
+                Ok(e)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.pos += 1;
+                }
+                let number: String = self.chars[start..self.pos].iter().collect();
+                number
+                    .parse()
+                    .map(Expression::Number)
+                    .map_err(|_| format!("invalid number '{number}'"))
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                if self.eat('(') {
+                    let mut args = vec![];
+                    if !self.eat(')') {
+                        loop {
+                            args.push(self.expression()?);
+                            if self.eat(')') {
+                                break;
+                            }
+                            if !self.eat(',') {
+                                return Err(format!("expected ',' or ')' at {}", self.pos));
+                            }
+                        }
+                    }
+                    Ok(Expression::Call(name, args))
+                } else {
+                    Ok(Expression::Variable(name))
+                }
+            }
+            Some(c) => Err(format!("unexpected '{c}' at {start}")),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Scene;
+
+    fn eval(source: &str) -> Result<f32, String> {
+        let variables = |name: &str| match name {
+            "w" => Some(4.0),
+            "margin" => Some(0.5),
+            _ => None,
+        };
+        Expression::parse(source)?.eval(&variables)
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(7.0));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(9.0));
+        assert_eq!(eval("w/2 + margin"), Ok(2.5));
+        assert_eq!(eval("8 - 4 - 2"), Ok(2.0));
+        assert_eq!(eval("8 / 4 / 2"), Ok(1.0));
+        assert_eq!(eval("2 * 3 ^ 2"), Ok(18.0));
+    }
+
+    #[test]
+    fn power() {
+        // right associative, binding tighter than unary minus but not than its exponent's
+        assert_eq!(eval("2 ^ 3 ^ 2"), Ok(512.0));
+        assert_eq!(eval("-2 ^ 2"), Ok(-4.0));
+        assert_eq!(eval("2 ^ -1"), Ok(0.5));
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(eval("-w"), Ok(-4.0));
+        assert_eq!(eval("--w"), Ok(4.0));
+        assert_eq!(eval("3 - -w"), Ok(7.0));
+        assert_eq!(eval("-w * 2"), Ok(-8.0));
+    }
+
+    #[test]
+    fn calls() {
+        assert_eq!(eval("abs(-3)"), Ok(3.0));
+        assert_eq!(eval("sqrt(w)"), Ok(2.0));
+        assert_eq!(eval("min(w, margin) + max(1, 2)"), Ok(2.5));
+        assert_eq!(eval("cos(pi)"), Ok(-1.0));
+        assert_eq!(eval("sin(0)"), Ok(0.0));
+        assert!(eval("min(1)")
+            .unwrap_err()
+            .contains("unknown function 'min'"));
+        assert!(eval("foo(1, 2)")
+            .unwrap_err()
+            .contains("unknown function 'foo'"));
+        assert_eq!(
+            Expression::parse("max(a, b) * pi").unwrap().variables(),
+            vec!["a", "b"]
+        );
+    }
+
+    #[test]
+    fn unknown_variables() {
+        assert_eq!(
+            eval("w + height"),
+            Err("unknown variable 'height'".to_string())
+        );
+    }
+
+    #[test]
+    fn syntax_errors() {
+        for source in ["", "1 +", "(1 + 2", "1 2", "1..2", "min(1 2)", "2 * )", "#"] {
+            assert!(Expression::parse(source).is_err(), "{source:?} parsed");
+        }
+        assert_eq!(
+            Expression::parse("(1 + 2"),
+            Err("missing ')' at 6".to_string())
+        );
+        assert_eq!(
+            Expression::parse("1 2"),
+            Err("unexpected '2' at 2".to_string())
+        );
+    }
+
+    #[test]
+    fn variable_cycles() {
+        let mut scene = Scene::new()
+            .variable("a", "b + 1")
+            .variable("b", "2 * a")
+            .variable("c", "3");
+        scene.apply_parameters();
+        let errors = scene.parameter_errors();
+        assert_eq!(errors.len(), 2);
+        assert!(
+            errors[0].contains("dependency cycle a -> b -> a"),
+            "{errors:?}"
+        );
+        assert_eq!(scene.variable_values().get("c"), Some(&3.0));
+    }
+}
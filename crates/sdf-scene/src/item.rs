@@ -0,0 +1,210 @@
+use crate::animation::Track;
+use dfutils::primitives_enum::Shape;
+use serde::{Deserialize, Serialize};
+use shared::sdf_interpreter::{Modifier, Operator, Transform};
+use std::collections::BTreeMap;
+
+#[repr(C)]
+#[derive(
+    bytemuck::Pod,
+    bytemuck::Zeroable,
+    Hash,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Serialize,
+    Deserialize,
+)]
+pub struct ItemId(pub u32);
+
+impl ItemId {
+    /// A random id, unlikely to collide with the ids of a scene
+    pub fn random() -> Self {
+        Self(rand::random())
+    }
+}
+
+impl std::fmt::Debug for ItemId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:04x}", self.0)
+    }
+}
+
+#[derive(Hash, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ComponentId(pub u32);
+
+impl ComponentId {
+    pub fn random() -> Self {
+        Self(rand::random())
+    }
+}
+
+impl std::fmt::Debug for ComponentId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "@{:04x}", self.0)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Item {
+    Operator(Operator, Vec<ItemId>),
+    Shape(Shape, Transform),
+    /// A placement of a component, expanded with its own transform when generating instructions
+    Instance(ComponentId, Transform),
+    /// Interpolation between the distances of two children by a factor in [0, 1]
+    Lerp(f32, Vec<ItemId>),
+    /// Change to the distance of a single child
+    Modifier(Modifier, Vec<ItemId>),
+}
+
+impl Item {
+    /// Children of the items which are containers
+    pub fn children(&self) -> Option<&Vec<ItemId>> {
+        match self {
+            Item::Operator(_, children) | Item::Lerp(_, children) | Item::Modifier(_, children) => {
+                Some(children)
+            }
+            Item::Shape(_, _) | Item::Instance(_, _) => None,
+        }
+    }
+
+    pub(crate) fn children_mut(&mut self) -> Option<&mut Vec<ItemId>> {
+        match self {
+            Item::Operator(_, children) | Item::Lerp(_, children) | Item::Modifier(_, children) => {
+                Some(children)
+            }
+            Item::Shape(_, _) | Item::Instance(_, _) => None,
+        }
+    }
+
+    /// Maximum number of children of a container, if limited
+    pub fn capacity(&self) -> Option<usize> {
+        match self {
+            Item::Lerp(_, _) => Some(2),
+            Item::Modifier(_, _) => Some(1),
+            _ => None,
+        }
+    }
+}
+
+impl From<Shape> for Item {
+    fn from(shape: Shape) -> Self {
+        Item::Shape(shape, Default::default())
+    }
+}
+
+impl From<Operator> for Item {
+    fn from(op: Operator) -> Self {
+        Item::Operator(op, Default::default())
+    }
+}
+
+/// A subtree defined once and referenced by any number of instances
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Component {
+    pub name: String,
+
+    /// Union operator holding the definition, outside of the composition tree
+    pub root_id: ItemId,
+}
+
+/// User settings of an item besides its geometry
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ItemProperties {
+    /// Shown in the composition tree instead of the item type, if not empty
+    pub name: String,
+
+    /// Hidden items are left out of the composition without being deleted
+    pub hidden: bool,
+
+    /// Locked items can't be selected or moved in the canvas
+    pub locked: bool,
+
+    /// Expressions driving parameters of the item, by parameter name
+    pub bindings: BTreeMap<String, String>,
+
+    /// Keyframes animating parameters of the item, by parameter name
+    pub tracks: BTreeMap<String, Track>,
+}
+
+/// A named scene variable, usable in the expressions of other variables and of bindings
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Variable {
+    pub name: String,
+    pub expression: String,
+}
+
+/// A subtree to add to a [Scene](crate::Scene), holding its children instead of their ids
+#[derive(Clone, Debug)]
+pub enum Node {
+    Operator(Operator, Vec<Node>),
+    Shape(Shape, Transform),
+    Instance(ComponentId, Transform),
+    Lerp(f32, Box<(Node, Node)>),
+    Modifier(Modifier, Box<Node>),
+}
+
+impl Node {
+    pub fn operator<N: Into<Node>>(op: Operator, children: impl IntoIterator<Item = N>) -> Self {
+        Node::Operator(op, children.into_iter().map(Into::into).collect())
+    }
+
+    pub fn union<N: Into<Node>>(children: impl IntoIterator<Item = N>) -> Self {
+        Self::operator(Operator::Union, children)
+    }
+
+    pub fn intersect<N: Into<Node>>(children: impl IntoIterator<Item = N>) -> Self {
+        Self::operator(Operator::Intersect, children)
+    }
+
+    /// The first child minus all the others
+    pub fn subtract<N: Into<Node>>(children: impl IntoIterator<Item = N>) -> Self {
+        Self::operator(Operator::Subtract, children)
+    }
+
+    pub fn xor<N: Into<Node>>(children: impl IntoIterator<Item = N>) -> Self {
+        Self::operator(Operator::Xor, children)
+    }
+
+    pub fn lerp(t: f32, a: impl Into<Node>, b: impl Into<Node>) -> Self {
+        Node::Lerp(t.clamp(0.0, 1.0), Box::new((a.into(), b.into())))
+    }
+
+    pub fn modify(modifier: Modifier, child: impl Into<Node>) -> Self {
+        Node::Modifier(modifier, Box::new(child.into()))
+    }
+
+    /// Place every shape and instance of the subtree with `transform`, on top of their own
+    pub fn transformed(self, transform: Transform) -> Self {
+        match self {
+            Node::Operator(op, children) => Node::Operator(
+                op,
+                children
+                    .into_iter()
+                    .map(|child| child.transformed(transform))
+                    .collect(),
+            ),
+            Node::Shape(shape, t) => Node::Shape(shape, transform.compose(&t)),
+            Node::Instance(component_id, t) => Node::Instance(component_id, transform.compose(&t)),
+            Node::Lerp(t, children) => {
+                let (a, b) = *children;
+                Node::Lerp(
+                    t,
+                    Box::new((a.transformed(transform), b.transformed(transform))),
+                )
+            }
+            Node::Modifier(modifier, child) => {
+                Node::Modifier(modifier, Box::new(child.transformed(transform)))
+            }
+        }
+    }
+}
+
+impl From<Shape> for Node {
+    fn from(shape: Shape) -> Self {
+        Node::Shape(shape, Default::default())
+    }
+}
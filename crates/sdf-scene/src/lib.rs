@@ -0,0 +1,317 @@
+//! Compositions of 2D sdfs as edited in the runner, usable without it
+//!
+//! ```
+//! # use dfutils::{primitives::*, primitives_enum::Shape, sdf::Sdf};
+//! # use glam::vec2;
+//! # use sdf_scene::Scene;
+//! # use shared::sdf_interpreter::SdfInstructions;
+//! let scene = Scene::new()
+//!     .union([Shape::Disk(Disk::new(0.2)), Shape::Rectangle(Rectangle::new(0.8, 0.1))])
+//!     .subtract([Shape::Disk(Disk::new(0.05))]);
+//! let instructions = scene.generate_instructions();
+//! let d = SdfInstructions::new(&instructions).signed_distance(vec2(0.1, 0.0));
+//! assert!(d.d < 0.0);
+//! ```
+
+pub mod animation;
+pub mod expression;
+mod item;
+pub mod parameters;
+mod scene;
+
+pub use item::{Component, ComponentId, Item, ItemId, ItemProperties, Node, Variable};
+pub use scene::Scene;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dfutils::{
+        grid::Grid,
+        interval::{Aabb, Prune},
+        noise::Noise,
+        outline::Outline,
+        primitives::{Disk, Rectangle},
+        primitives_enum::Shape,
+        sdf::Sdf,
+    };
+    use glam::{vec2, Vec2};
+    use shared::sdf_interpreter::{Modifier, Operator, SdfInstructions, Transform};
+
+    fn disk(radius: f32, x: f32) -> Node {
+        Node::Shape(
+            Shape::Disk(Disk::new(radius)),
+            Transform::from_position(vec2(x, 0.0)),
+        )
+    }
+
+    fn distance(scene: &Scene, p: Vec2) -> f32 {
+        let instructions = scene.generate_instructions();
+        SdfInstructions::new(&instructions).signed_distance(p).d
+    }
+
+    #[test]
+    fn builder() {
+        let scene = Scene::new()
+            .union([disk(0.2, -0.2), disk(0.2, 0.2)])
+            .subtract([disk(0.05, 0.2), disk(0.05, -0.2)]);
+        assert!((distance(&scene, vec2(0.1, 0.0)) + 0.05).abs() < 1e-5);
+        assert!((distance(&scene, vec2(0.2, 0.0)) - 0.05).abs() < 1e-5);
+        assert!((distance(&scene, vec2(-0.2, 0.0)) - 0.05).abs() < 1e-5);
+        assert!((distance(&scene, vec2(0.6, 0.0)) - 0.2).abs() < 1e-5);
+
+        // the shapes subtracted one after the other go to the same operator
+        let [top] = scene.root_children()[..] else {
+            panic!("expected a single top level item")
+        };
+        let Some(Item::Operator(Operator::Subtract, children)) = scene.item(top) else {
+            panic!("expected a subtraction")
+        };
+        assert_eq!(children.len(), 3);
+    }
+
+    #[test]
+    fn batched() {
+        let bar = Node::Shape(
+            Shape::Rectangle(Rectangle::new(0.6, 0.05)),
+            Transform {
+                position: vec2(0.1, 0.0),
+                rotation: 0.3,
+            },
+        );
+        let scene = Scene::new()
+            .union([disk(0.2, -0.2), disk(0.1, 0.3)])
+            .subtract([bar])
+            .xor([Node::lerp(0.3, disk(0.1, 0.0), disk(0.2, 0.1))])
+            .union([Node::modify(
+                Modifier::Displace(Noise::default()),
+                disk(0.05, -0.4),
+            )]);
+        let outlined = scene.clone().union([Node::modify(
+            Modifier::Outline(Outline::new(0.0, 0.01, 0.02, 0.02)),
+            disk(0.1, 0.3),
+        )]);
+        // more points than are evaluated together, and not a multiple of the lanes
+        let points: Vec<Vec2> = (0..150)
+            .map(|i| Vec2::from_angle(i as f32) * (i as f32 / 250.0))
+            .collect();
+        for scene in [scene, outlined] {
+            let instructions = scene.generate_instructions();
+            let sdf = SdfInstructions::new(&instructions);
+            let mut out = vec![Default::default(); points.len()];
+            sdf.signed_distance_many(&points, &mut out);
+            for (p, d) in points.iter().zip(&out) {
+                let expected = sdf.signed_distance(*p);
+                assert!((d.d - expected.d).abs() < 1e-5);
+                assert_eq!({ d.data }, { expected.data });
+            }
+        }
+    }
+
+    #[test]
+    fn pruning() {
+        const SIZE: usize = 128;
+        const BAND: f32 = 0.02;
+
+        let scene = Scene::new()
+            .union([disk(0.1, -0.4), disk(0.2, 0.0), disk(0.1, 0.4)])
+            .subtract([disk(0.05, 0.0)])
+            .union([Node::modify(
+                Modifier::Outline(Outline::new(0.0, 0.01, 0.02, 0.02)),
+                disk(0.1, 0.4),
+            )]);
+        let instructions = scene.generate_instructions();
+        let sdf = SdfInstructions::new(&instructions);
+
+        // around the left disk, the others can't win the union
+        let (pruned, _) = sdf.prune(Aabb::new(vec2(-0.45, -0.05), vec2(-0.35, 0.05)));
+        assert_eq!(pruned.len(), 1);
+
+        let reference = Grid::from_sdf(SIZE, SIZE, &sdf);
+        let mut grid = Grid::new(SIZE, SIZE);
+        grid.rasterize(&sdf, BAND);
+        for (d, expected) in grid.buffer.iter().zip(&reference.buffer) {
+            if expected.d.abs() <= BAND {
+                assert_eq!({ d.d }, { expected.d });
+                assert_eq!({ d.data }, { expected.data });
+            } else {
+                assert_eq!(d.d > 0.0, expected.d > 0.0);
+                assert!(d.d.abs() <= expected.d.abs());
+            }
+        }
+    }
+
+    #[test]
+    fn editing() {
+        let mut scene = Scene::new().union([Node::union([disk(0.1, 0.0), disk(0.1, 0.3)])]);
+        let group = scene.root_children()[0];
+        let first = scene.item(group).unwrap().children().unwrap()[0];
+        assert_eq!(scene.parent_id(first), Some(group));
+
+        scene.move_item(first, scene.root_id(), 0);
+        assert_eq!(scene.root_children(), &vec![first, group]);
+        assert_eq!(scene.parent_id(first), None);
+
+        scene.remove_item(group);
+        assert_eq!(scene.root_children(), &vec![first]);
+        assert_eq!(scene.item_count(), 1);
+        assert!((distance(&scene, vec2(0.3, 0.0)) - 0.2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn components() {
+        let mut scene = Scene::new();
+        let component = scene.add_component("dot", disk(0.1, 0.0));
+        let placement = Transform::from_position(vec2(0.5, 0.0));
+        scene = scene.union([Node::Instance(component, placement)]);
+        assert!((distance(&scene, vec2(0.5, 0.0)) + 0.1).abs() < 1e-5);
+
+        let instance = scene.root_children()[0];
+        let copy = scene.detach_instance(instance).unwrap();
+        assert_eq!(scene.label(copy), "dot");
+        scene.remove_component(component);
+        assert_eq!(scene.components().count(), 0);
+        assert!((distance(&scene, vec2(0.5, 0.0)) + 0.1).abs() < 1e-5);
+    }
+
+    #[test]
+    fn displacement_moves_with_items() {
+        let displaced = |x: f32| {
+            Scene::new().union([Node::modify(
+                Modifier::Displace(Noise::default()),
+                disk(0.1, x),
+            )])
+        };
+        let (scene, moved) = (displaced(0.0), displaced(0.3));
+        for p in [vec2(0.05, 0.02), vec2(0.1, -0.1), vec2(-0.2, 0.0)] {
+            assert!((distance(&scene, p) - distance(&moved, p + vec2(0.3, 0.0))).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn component_cycles() {
+        let mut scene = Scene::new();
+        let a = scene.add_component("a", Node::union([disk(0.1, 0.0), disk(0.1, 0.5)]));
+        let b = scene.add_component("b", Node::Instance(a, Transform::default()));
+        let a_root = scene.component(a).unwrap().root_id;
+        let b_root = scene.component(b).unwrap().root_id;
+
+        // neither a nor b can hold an instance of a, even through b
+        let count = scene.item_count();
+        for component in [a, b] {
+            let instance = Item::Instance(component, Transform::default());
+            assert!(!scene.can_contain(a_root, &instance));
+            scene.add_item(instance, ItemId::random(), a_root, 0);
+        }
+        assert!(!scene.can_contain(b_root, &Item::Instance(b, Transform::default())));
+        assert!(scene.can_contain(b_root, &Item::Instance(a, Transform::default())));
+        assert!(scene
+            .add(Node::Instance(b, Transform::default()), a_root, 0)
+            .is_none());
+        assert_eq!(scene.item_count(), count);
+
+        // cycles of older files are left out when generating instructions
+        let group = scene.container(a_root).unwrap()[0];
+        let second = scene.container(group).unwrap()[1];
+        scene.edit_item(Item::Instance(a, Transform::default()), second);
+        scene = scene.union([Node::Instance(b, Transform::default())]);
+        assert!((distance(&scene, vec2(0.0, 0.0)) + 0.1).abs() < 1e-5);
+    }
+
+    #[test]
+    fn bindings() {
+        let mut scene = Scene::new()
+            .variable("r", "2 * base")
+            .variable("base", "0.1")
+            .union([disk(0.1, 0.0)]);
+        let id = scene.root_children()[0];
+        let mut properties = scene.properties(id);
+        properties
+            .bindings
+            .insert("radius".to_string(), "r".to_string());
+        scene.set_properties(id, properties);
+        scene.apply_parameters();
+        assert!(scene.parameter_errors().is_empty());
+        assert!((distance(&scene, Vec2::ZERO) + 0.2).abs() < 1e-5);
+
+        scene.set_variables(vec![Variable {
+            name: "r".to_string(),
+            expression: "r + 1".to_string(),
+        }]);
+        scene.apply_parameters();
+        assert_eq!(scene.parameter_errors().len(), 2);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let scene = Scene::new().union([disk(0.2, 0.0)]).xor([Node::lerp(
+            0.5,
+            disk(0.1, 0.1),
+            disk(0.3, 0.1),
+        )]);
+        let mut file = Vec::new();
+        scene.write(&mut file).unwrap();
+        let loaded = Scene::read(file.as_slice()).unwrap();
+        assert_eq!(loaded.item_count(), scene.item_count());
+        for p in [vec2(0.0, 0.0), vec2(0.15, 0.0), vec2(0.3, 0.1)] {
+            assert_eq!(distance(&loaded, p), distance(&scene, p));
+        }
+
+        // saving an unchanged scene gives the same file
+        let mut again = Vec::new();
+        loaded.write(&mut again).unwrap();
+        assert_eq!(again, file);
+    }
+
+    #[test]
+    fn load_malformed() {
+        use serde_json::{json, Value};
+
+        let scene = Scene::new().union([disk(0.2, 0.0), disk(0.1, 0.3)]);
+        let mut file = Vec::new();
+        scene.write(&mut file).unwrap();
+        let valid: Value = serde_json::from_slice(&file).unwrap();
+        let (root, child) = (scene.root_id().0, scene.root_children()[0].0);
+        let missing = root.wrapping_add(1);
+        let item = |json: &mut Value, id: u32| -> usize {
+            let items = json["items"].as_array().unwrap();
+            items.iter().position(|item| item["id"] == id).unwrap()
+        };
+        let edits: [&dyn Fn(&mut Value); 7] = [
+            &|json| json["root_id"] = missing.into(),
+            &|json| json["root_id"] = child.into(),
+            &|json| {
+                let i = item(json, child);
+                json["items"].as_array_mut().unwrap().remove(i);
+            },
+            &|json| {
+                let i = item(json, root);
+                json["items"][i]["item"]["Operator"][1] = json!([child, child]);
+            },
+            &|json| {
+                let first = json["items"][0].clone();
+                json["items"].as_array_mut().unwrap().push(first);
+            },
+            &|json| json["components"] = json!([[1, {"name": "a", "root_id": missing}]]),
+            &|json| json["components"] = json!([[1, {"name": "a", "root_id": child}]]),
+        ];
+        for edit in edits {
+            let mut json = valid.clone();
+            edit(&mut json);
+            let error = Scene::read(json.to_string().as_bytes()).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "{error}");
+        }
+        assert!(Scene::read(&file[..file.len() / 2]).is_err());
+        assert!(Scene::read(valid.to_string().as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn instruction_size() {
+        use shared::{sdf_interpreter::Instruction, sdf_wrapper::SdfWrapper};
+        use std::mem::size_of;
+
+        // capsule chains are the largest shapes, the instruction of a disk only takes 36 bytes
+        assert_eq!(size_of::<Shape>(), 200);
+        assert_eq!(size_of::<Instruction<SdfWrapper<Shape, ItemId>>>(), 216);
+        assert_eq!(size_of::<Item>(), 216);
+    }
+}
@@ -0,0 +1,240 @@
+use dfutils::primitives::*;
+use dfutils::primitives_enum::Shape;
+use glam::*;
+use shared::sdf_interpreter::{Modifier, Transform};
+
+/// Numeric parameters which can be bound to expressions, addressed by name
+///
+/// Angles are in degrees, like the typed amounts of the transform mode.
+pub trait Parameters {
+    fn parameters(&self) -> Vec<(&'static str, f32)>;
+
+    /// Set parameter `name`, unknown names are ignored
+    fn set_parameter(self, name: &str, value: f32) -> Self;
+}
+
+fn angle_of(v: Vec2) -> f32 {
+    v.y.atan2(v.x).to_degrees()
+}
+
+impl Parameters for Disk {
+    fn parameters(&self) -> Vec<(&'static str, f32)> {
+        vec![("radius", self.radius)]
+    }
+
+    fn set_parameter(mut self, name: &str, value: f32) -> Self {
+        if name == "radius" {
+            self.radius = value.max(0.0);
+        }
+        self
+    }
+}
+
+impl Parameters for Torus {
+    fn parameters(&self) -> Vec<(&'static str, f32)> {
+        vec![
+            ("major_radius", self.major_radius),
+            ("minor_radius", self.minor_radius),
+        ]
+    }
+
+    fn set_parameter(mut self, name: &str, value: f32) -> Self {
+        match name {
+            "major_radius" => self.major_radius = value.max(0.0),
+            "minor_radius" => self.minor_radius = value.max(0.0),
+            _ => {}
+        }
+        self
+    }
+}
+
+impl Parameters for Rectangle {
+    fn parameters(&self) -> Vec<(&'static str, f32)> {
+        vec![("width", self.width), ("height", self.height)]
+    }
+
+    fn set_parameter(mut self, name: &str, value: f32) -> Self {
+        match name {
+            "width" => self.width = value.max(0.0),
+            "height" => self.height = value.max(0.0),
+            _ => {}
+        }
+        self
+    }
+}
+
+impl Parameters for Cross {
+    fn parameters(&self) -> Vec<(&'static str, f32)> {
+        vec![("length", self.length), ("thickness", self.thickness)]
+    }
+
+    fn set_parameter(mut self, name: &str, value: f32) -> Self {
+        match name {
+            "length" => self.length = value.max(0.0),
+            "thickness" => self.thickness = value.max(0.0),
+            _ => {}
+        }
+        self
+    }
+}
+
+impl Parameters for LineSegment {
+    fn parameters(&self) -> Vec<(&'static str, f32)> {
+        vec![
+            ("a.x", self.a.x),
+            ("a.y", self.a.y),
+            ("b.x", self.b.x),
+            ("b.y", self.b.y),
+        ]
+    }
+
+    fn set_parameter(mut self, name: &str, value: f32) -> Self {
+        match name {
+            "a.x" => self.a.x = value,
+            "a.y" => self.a.y = value,
+            "b.x" => self.b.x = value,
+            "b.y" => self.b.y = value,
+            _ => {}
+        }
+        self
+    }
+}
+
+/// The largest radius, setting it scales all of them
+impl Parameters for CapsuleChain {
+    fn parameters(&self) -> Vec<(&'static str, f32)> {
+        vec![("radius", self.radii().iter().copied().fold(0.0, f32::max))]
+    }
+
+    fn set_parameter(mut self, name: &str, value: f32) -> Self {
+        if name == "radius" {
+            let len = self.len as usize;
+            let radius = self.radii().iter().copied().fold(0.0, f32::max);
+            let value = value.max(0.0);
+            for r in &mut self.radii[..len] {
+                *r = if radius > 0.0 {
+                    *r * value / radius
+                } else {
+                    value
+                };
+            }
+        }
+        self
+    }
+}
+
+impl Parameters for Plane {
+    fn parameters(&self) -> Vec<(&'static str, f32)> {
+        vec![("angle", angle_of(self.normal))]
+    }
+
+    fn set_parameter(mut self, name: &str, value: f32) -> Self {
+        if name == "angle" {
+            self.normal = Vec2::from_angle(value.to_radians());
+        }
+        self
+    }
+}
+
+impl Parameters for Ray {
+    fn parameters(&self) -> Vec<(&'static str, f32)> {
+        vec![("angle", angle_of(self.direction))]
+    }
+
+    fn set_parameter(mut self, name: &str, value: f32) -> Self {
+        if name == "angle" {
+            self.direction = Vec2::from_angle(value.to_radians());
+        }
+        self
+    }
+}
+
+impl Parameters for Shape {
+    fn parameters(&self) -> Vec<(&'static str, f32)> {
+        match self {
+            Shape::Disk(shape) => shape.parameters(),
+            Shape::Torus(shape) => shape.parameters(),
+            Shape::Rectangle(shape) => shape.parameters(),
+            Shape::Cross(shape) => shape.parameters(),
+            Shape::LineSegment(shape) => shape.parameters(),
+            Shape::CapsuleChain(shape) => shape.parameters(),
+            Shape::Plane(shape) => shape.parameters(),
+            Shape::Ray(shape) => shape.parameters(),
+        }
+    }
+
+    fn set_parameter(self, name: &str, value: f32) -> Self {
+        match self {
+            Shape::Disk(shape) => shape.set_parameter(name, value).into(),
+            Shape::Torus(shape) => shape.set_parameter(name, value).into(),
+            Shape::Rectangle(shape) => shape.set_parameter(name, value).into(),
+            Shape::Cross(shape) => shape.set_parameter(name, value).into(),
+            Shape::LineSegment(shape) => shape.set_parameter(name, value).into(),
+            Shape::CapsuleChain(shape) => shape.set_parameter(name, value).into(),
+            Shape::Plane(shape) => shape.set_parameter(name, value).into(),
+            Shape::Ray(shape) => shape.set_parameter(name, value).into(),
+        }
+    }
+}
+
+impl Parameters for Transform {
+    fn parameters(&self) -> Vec<(&'static str, f32)> {
+        vec![
+            ("pos.x", self.position.x),
+            ("pos.y", self.position.y),
+            ("rot", self.rotation.to_degrees()),
+        ]
+    }
+
+    fn set_parameter(mut self, name: &str, value: f32) -> Self {
+        match name {
+            "pos.x" => self.position.x = value,
+            "pos.y" => self.position.y = value,
+            "rot" => self.rotation = value.to_radians(),
+            _ => {}
+        }
+        self
+    }
+}
+
+impl Parameters for Modifier {
+    fn parameters(&self) -> Vec<(&'static str, f32)> {
+        match self {
+            Modifier::Displace(noise) => vec![
+                ("frequency", noise.frequency),
+                ("amplitude", noise.amplitude),
+                ("seed", noise.seed as f32),
+            ],
+            Modifier::Outline(outline) => vec![
+                ("offset", outline.offset),
+                ("width", outline.width),
+                ("dash", outline.dash),
+                ("gap", outline.gap),
+            ],
+        }
+    }
+
+    fn set_parameter(self, name: &str, value: f32) -> Self {
+        match self {
+            Modifier::Displace(mut noise) => {
+                match name {
+                    "frequency" => noise.frequency = value.max(0.0),
+                    "amplitude" => noise.amplitude = value,
+                    "seed" => noise.seed = value.max(0.0) as u32,
+                    _ => {}
+                }
+                Modifier::Displace(noise)
+            }
+            Modifier::Outline(mut outline) => {
+                match name {
+                    "offset" => outline.offset = value,
+                    "width" => outline.width = value.max(0.0),
+                    "dash" => outline.dash = value.max(0.0),
+                    "gap" => outline.gap = value.max(0.0),
+                    _ => {}
+                }
+                Modifier::Outline(outline)
+            }
+        }
+    }
+}
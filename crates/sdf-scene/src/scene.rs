@@ -0,0 +1,1065 @@
+use crate::{
+    animation::Timeline,
+    expression::Expression,
+    item::{Component, ComponentId, Item, ItemId, ItemProperties, Node, Variable},
+    parameters::Parameters,
+};
+use dfutils::primitives_enum::Shape;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use shared::{
+    sdf_interpreter::{Instruction, Operator, Transform},
+    sdf_wrapper::SdfWrapper,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+/// An item as stored in a scene file
+#[derive(Serialize, Deserialize)]
+struct SceneItem {
+    id: ItemId,
+    item: Item,
+    #[serde(default)]
+    properties: ItemProperties,
+}
+
+/// Contents of a scene file
+#[derive(Serialize, Deserialize)]
+struct SceneFile {
+    root_id: ItemId,
+    items: Vec<SceneItem>,
+    #[serde(default)]
+    components: Vec<(ComponentId, Component)>,
+    #[serde(default)]
+    variables: Vec<Variable>,
+    #[serde(default)]
+    timeline: Timeline,
+}
+
+/// A composition tree, with the components, variables and animation it uses
+///
+/// Items are stored flat by id, containers referring to their children by id. The root is a union
+/// holding the top level items.
+#[derive(Clone, Debug)]
+pub struct Scene {
+    /// All items
+    items: HashMap<ItemId, Item>,
+
+    /// Properties of the items which have any set
+    properties: HashMap<ItemId, ItemProperties>,
+
+    /// Id of the root item
+    root_id: ItemId,
+
+    /// Component definitions, whose items are stored in `items` but aren't part of the root
+    components: BTreeMap<ComponentId, Component>,
+
+    /// Scene variables, in the order they are displayed
+    variables: Vec<Variable>,
+
+    /// Values of the variables as of the last evaluation
+    variable_values: HashMap<String, f32>,
+
+    /// Errors of the last evaluation of variables and bindings
+    parameter_errors: Vec<String>,
+
+    pub timeline: Timeline,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        let root_id = ItemId::random();
+        Self {
+            items: std::iter::once((root_id, Item::Operator(Operator::Union, Vec::new())))
+                .collect(),
+            properties: HashMap::new(),
+            root_id,
+            components: BTreeMap::new(),
+            variables: Vec::new(),
+            variable_values: HashMap::new(),
+            parameter_errors: Vec::new(),
+            timeline: Timeline::default(),
+        }
+    }
+}
+
+//
+// Builder
+//
+impl Scene {
+    /// An empty scene
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `nodes` to the top level of the composition.
+    pub fn union<N: Into<Node>>(mut self, nodes: impl IntoIterator<Item = N>) -> Self {
+        for node in nodes {
+            self.combine(Operator::Union, node.into());
+        }
+        self
+    }
+
+    /// Intersect the composition so far with each of `nodes`.
+    pub fn intersect<N: Into<Node>>(mut self, nodes: impl IntoIterator<Item = N>) -> Self {
+        for node in nodes {
+            self.combine(Operator::Intersect, node.into());
+        }
+        self
+    }
+
+    /// Subtract each of `nodes` from the composition so far.
+    pub fn subtract<N: Into<Node>>(mut self, nodes: impl IntoIterator<Item = N>) -> Self {
+        for node in nodes {
+            self.combine(Operator::Subtract, node.into());
+        }
+        self
+    }
+
+    pub fn xor<N: Into<Node>>(mut self, nodes: impl IntoIterator<Item = N>) -> Self {
+        for node in nodes {
+            self.combine(Operator::Xor, node.into());
+        }
+        self
+    }
+
+    /// Add a variable, usable in bindings and in the expressions of later variables.
+    pub fn variable(mut self, name: &str, expression: &str) -> Self {
+        self.variables.push(Variable {
+            name: name.to_string(),
+            expression: expression.to_string(),
+        });
+        self
+    }
+}
+
+//
+// Editing
+//
+impl Scene {
+    pub fn root_id(&self) -> ItemId {
+        self.root_id
+    }
+
+    /// Top level items of the composition
+    pub fn root_children(&self) -> &Vec<ItemId> {
+        let Some(Item::Operator(_, children)) = self.items.get(&self.root_id) else {
+            panic!("no root")
+        };
+        children
+    }
+
+    fn root_children_mut(&mut self) -> &mut Vec<ItemId> {
+        let Some(Item::Operator(_, children)) = self.items.get_mut(&self.root_id) else {
+            panic!("no root")
+        };
+        children
+    }
+
+    pub fn item(&self, id: ItemId) -> Option<&Item> {
+        self.items.get(&id)
+    }
+
+    /// Number of items, including those of component definitions but not the root
+    pub fn item_count(&self) -> usize {
+        self.items.len() - 1
+    }
+
+    /// Children of an item, if it is a container
+    pub fn container(&self, id: ItemId) -> Option<&Vec<ItemId>> {
+        self.items.get(&id).and_then(Item::children)
+    }
+
+    /// Does some container contain the given item?
+    ///
+    /// Used to test if a target location is suitable for a given dragged item.
+    pub fn contains(&self, container_id: ItemId, item_id: ItemId) -> bool {
+        if let Some(children) = self.container(container_id) {
+            if container_id == item_id {
+                return true;
+            }
+
+            if children.contains(&item_id) {
+                return true;
+            }
+
+            for child_id in children {
+                if self.contains(*child_id, item_id) {
+                    return true;
+                }
+            }
+
+            return false;
+        }
+
+        false
+    }
+
+    /// Whether `item` can be put in `container_id` without a component ending up containing an
+    /// instance of itself, directly or through other components
+    pub fn can_contain(&self, container_id: ItemId, item: &Item) -> bool {
+        match self.component_of(container_id) {
+            Some(component_id) => !self.instantiates(item, component_id, &mut vec![]),
+            None => true,
+        }
+    }
+
+    /// The component whose definition holds `item_id`, if any
+    fn component_of(&self, item_id: ItemId) -> Option<ComponentId> {
+        let mut id = item_id;
+        while let Some((parent_id, _)) = self.parent_and_pos(id) {
+            id = parent_id;
+        }
+        self.components
+            .iter()
+            .find(|(_, component)| component.root_id == id)
+            .map(|(component_id, _)| *component_id)
+    }
+
+    /// Whether `item` holds an instance of `component_id`, directly or within the definitions of
+    /// the components it instantiates, the ones in `visited` being skipped
+    fn instantiates(
+        &self,
+        item: &Item,
+        component_id: ComponentId,
+        visited: &mut Vec<ComponentId>,
+    ) -> bool {
+        match item {
+            Item::Instance(id, _) => {
+                if *id == component_id {
+                    return true;
+                }
+                if visited.contains(id) {
+                    return false;
+                }
+                visited.push(*id);
+                self.components
+                    .get(id)
+                    .and_then(|component| self.items.get(&component.root_id))
+                    .is_some_and(|root| self.instantiates(root, component_id, visited))
+            }
+            _ => item.children().into_iter().flatten().any(|child_id| {
+                self.items
+                    .get(child_id)
+                    .is_some_and(|child| self.instantiates(child, component_id, visited))
+            }),
+        }
+    }
+
+    /// Move item `item_id` to `container_id` at position `pos`.
+    ///
+    /// Does nothing if that would make a component contain an instance of itself.
+    pub fn move_item(&mut self, item_id: ItemId, container_id: ItemId, mut pos: usize) {
+        if !self
+            .items
+            .get(&item_id)
+            .is_some_and(|item| self.can_contain(container_id, item))
+        {
+            return;
+        }
+        // Remove the item from its current location. Note: we must adjust the target position if the item is
+        // moved within the same container, as the removal might shift the positions by one.
+        if let Some((source_parent_id, source_pos)) = self.parent_and_pos(item_id) {
+            if let Some(children) = self
+                .items
+                .get_mut(&source_parent_id)
+                .and_then(Item::children_mut)
+            {
+                children.remove(source_pos);
+            }
+
+            if source_parent_id == container_id && source_pos < pos {
+                pos -= 1;
+            }
+        }
+
+        if let Some(children) = self
+            .items
+            .get_mut(&container_id)
+            .and_then(Item::children_mut)
+        {
+            children.insert(pos.min(children.len()), item_id);
+        }
+    }
+
+    /// Add item `item_id` to `container_id` at position `pos`.
+    ///
+    /// Does nothing if that would make a component contain an instance of itself.
+    pub fn add_item(&mut self, item: Item, item_id: ItemId, container_id: ItemId, pos: usize) {
+        if !self.can_contain(container_id, &item) {
+            return;
+        }
+        self.items.insert(item_id, item);
+
+        if let Some(children) = self
+            .items
+            .get_mut(&container_id)
+            .and_then(Item::children_mut)
+        {
+            children.insert(pos.min(children.len()), item_id);
+        }
+    }
+
+    /// Add `node` and everything it contains to `container_id` at position `pos`, returns the id
+    /// of its top item, or `None` if that would make a component contain an instance of itself.
+    pub fn add(&mut self, node: Node, container_id: ItemId, pos: usize) -> Option<ItemId> {
+        let id = self.insert_node(node);
+        if !self.can_contain(container_id, &self.items[&id]) {
+            self.remove_item(id);
+            return None;
+        }
+        self.move_item(id, container_id, pos);
+        Some(id)
+    }
+
+    /// Store the items of `node` under new ids, outside of any container.
+    fn insert_node(&mut self, node: Node) -> ItemId {
+        let id = ItemId::random();
+        let item = match node {
+            Node::Operator(op, children) => Item::Operator(
+                op,
+                children
+                    .into_iter()
+                    .map(|child| self.insert_node(child))
+                    .collect(),
+            ),
+            Node::Shape(shape, transform) => Item::Shape(shape, transform),
+            Node::Instance(component_id, transform) => Item::Instance(component_id, transform),
+            Node::Lerp(t, children) => {
+                let (a, b) = *children;
+                Item::Lerp(t, vec![self.insert_node(a), self.insert_node(b)])
+            }
+            Node::Modifier(modifier, child) => {
+                Item::Modifier(modifier, vec![self.insert_node(*child)])
+            }
+        };
+        self.items.insert(id, item);
+        id
+    }
+
+    /// Combine `node` with the top level of the composition by `op`, returns the id of its top
+    /// item.
+    pub fn combine(&mut self, op: Operator, node: Node) -> ItemId {
+        let item_id = self.insert_node(node);
+        self.place_at_top(op, item_id);
+        item_id
+    }
+
+    /// Combine item `item_id` with the top level of the composition by `op`.
+    pub fn combine_item(&mut self, op: Operator, item: Item, item_id: ItemId) {
+        self.items.insert(item_id, item);
+        self.place_at_top(op, item_id);
+    }
+
+    /// A union puts the item next to the top level items. Other operators take the union of the
+    /// top level items as their first operand, unless it is a single operator of the same kind,
+    /// which the item is then appended to.
+    fn place_at_top(&mut self, op: Operator, item_id: ItemId) {
+        let children = self.root_children().clone();
+        if op == Operator::Union || children.is_empty() {
+            self.root_children_mut().push(item_id);
+        } else if children.len() == 1 {
+            let child = children[0];
+            match self.items.get_mut(&child) {
+                Some(Item::Operator(child_op, children)) if *child_op == op => {
+                    children.push(item_id);
+                }
+                _ => {
+                    let container_op_id = ItemId::random();
+                    self.root_children_mut()[0] = container_op_id;
+                    self.items
+                        .insert(container_op_id, Item::Operator(op, vec![child, item_id]));
+                }
+            }
+        } else {
+            let container_op_id = ItemId::random();
+            let container_union_id = ItemId::random();
+            self.items.insert(
+                container_op_id,
+                Item::Operator(op, vec![container_union_id, item_id]),
+            );
+            self.items.insert(
+                container_union_id,
+                Item::Operator(Operator::Union, children),
+            );
+            let children = self.root_children_mut();
+            children.truncate(1);
+            children[0] = container_op_id;
+        }
+    }
+
+    /// Replace item `item_id`, keeping its place in the tree.
+    pub fn edit_item(&mut self, item: Item, item_id: ItemId) {
+        if let Some(existing) = self.items.get_mut(&item_id) {
+            *existing = item;
+        }
+    }
+
+    /// Remove item `item_id` and everything it contains.
+    pub fn remove_item(&mut self, item_id: ItemId) {
+        // the item may already be gone, e.g. an instance inside a removed component
+        let Some(item) = self.items.get(&item_id) else {
+            return;
+        };
+        for id in item.children().cloned().into_iter().flatten() {
+            self.remove_item(id);
+        }
+        if let Some((id, pos)) = self.parent_and_pos(item_id) {
+            if let Some(items) = self.items.get_mut(&id).and_then(Item::children_mut) {
+                items.remove(pos);
+            }
+        }
+        self.items.remove(&item_id);
+        self.properties.remove(&item_id);
+    }
+
+    /// Find the parent of an item, and the index of that item within the parent's children.
+    ///
+    /// Items of component definitions are found too.
+    pub fn parent_and_pos(&self, id: ItemId) -> Option<(ItemId, usize)> {
+        if id == self.root_id || self.is_component_root(id) {
+            None
+        } else {
+            std::iter::once(self.root_id)
+                .chain(self.components.values().map(|c| c.root_id))
+                .find_map(|container_id| self.parent_and_pos_impl(id, container_id))
+        }
+    }
+
+    fn parent_and_pos_impl(&self, id: ItemId, container_id: ItemId) -> Option<(ItemId, usize)> {
+        if let Some(children) = self.container(container_id) {
+            for (idx, child_id) in children.iter().enumerate() {
+                if child_id == &id {
+                    return Some((container_id, idx));
+                } else if self.container(*child_id).is_some() {
+                    let res = self.parent_and_pos_impl(id, *child_id);
+                    if res.is_some() {
+                        return res;
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Find the operator containing an item, unless that is the root of the tree or of a
+    /// component.
+    pub fn parent_id(&self, id: ItemId) -> Option<ItemId> {
+        self.parent_and_pos(id)
+            .map(|(parent_id, _)| parent_id)
+            .filter(|parent_id| *parent_id != self.root_id && !self.is_component_root(*parent_id))
+    }
+
+    fn is_component_root(&self, id: ItemId) -> bool {
+        self.components.values().any(|c| c.root_id == id)
+    }
+
+    pub fn component(&self, id: ComponentId) -> Option<&Component> {
+        self.components.get(&id)
+    }
+
+    pub fn components(&self) -> impl Iterator<Item = (ComponentId, &Component)> {
+        self.components
+            .iter()
+            .map(|(id, component)| (*id, component))
+    }
+
+    /// Define a component from `node`, outside of the composition tree.
+    pub fn add_component(&mut self, name: &str, node: Node) -> ComponentId {
+        let component_id = ComponentId::random();
+        let item_id = self.insert_node(node);
+        let root_id = ItemId::random();
+        self.items
+            .insert(root_id, Item::Operator(Operator::Union, vec![item_id]));
+        let name = name.to_string();
+        self.components
+            .insert(component_id, Component { name, root_id });
+        component_id
+    }
+
+    pub fn rename_component(&mut self, component_id: ComponentId, name: String) {
+        if let Some(component) = self.components.get_mut(&component_id) {
+            component.name = name;
+        }
+    }
+
+    /// Move `item_id` into the definition of a new component, replacing it with an instance whose
+    /// id is returned.
+    pub fn make_component(&mut self, item_id: ItemId) -> Option<ItemId> {
+        let (parent_id, pos) = self.parent_and_pos(item_id)?;
+        let component_id = ComponentId::random();
+        let root_id = ItemId::random();
+        let instance_id = ItemId::random();
+        let name = self.label(item_id);
+        self.items
+            .insert(root_id, Item::Operator(Operator::Union, vec![item_id]));
+        self.items.insert(
+            instance_id,
+            Item::Instance(component_id, Transform::default()),
+        );
+        if let Some(children) = self.items.get_mut(&parent_id).and_then(Item::children_mut) {
+            children[pos] = instance_id;
+        }
+        self.components
+            .insert(component_id, Component { name, root_id });
+        Some(instance_id)
+    }
+
+    /// Replace instance `item_id` with an independent copy of its component's definition, whose
+    /// id is returned.
+    pub fn detach_instance(&mut self, item_id: ItemId) -> Option<ItemId> {
+        let Some(Item::Instance(component_id, transform)) = self.items.get(&item_id).cloned()
+        else {
+            return None;
+        };
+        let root_id = self.components.get(&component_id)?.root_id;
+        let (parent_id, pos) = self.parent_and_pos(item_id)?;
+        let copy_id = self.copy_subtree(root_id, &transform);
+        if let Some(children) = self.items.get_mut(&parent_id).and_then(Item::children_mut) {
+            children[pos] = copy_id;
+        }
+        self.items.remove(&item_id);
+        let mut properties = self.properties.remove(&item_id).unwrap_or_default();
+        if properties.name.is_empty() {
+            properties.name = self.components[&component_id].name.clone();
+        }
+        self.properties.insert(copy_id, properties);
+        Some(copy_id)
+    }
+
+    /// Copy an item and everything it contains under new ids, placing it with `transform`.
+    fn copy_subtree(&mut self, id: ItemId, transform: &Transform) -> ItemId {
+        let copy_id = ItemId::random();
+        let copy = match self.items.get(&id).cloned() {
+            Some(Item::Operator(op, children)) => Item::Operator(
+                op,
+                children
+                    .iter()
+                    .map(|child_id| self.copy_subtree(*child_id, transform))
+                    .collect(),
+            ),
+            Some(Item::Lerp(t, children)) => Item::Lerp(
+                t,
+                children
+                    .iter()
+                    .map(|child_id| self.copy_subtree(*child_id, transform))
+                    .collect(),
+            ),
+            Some(Item::Modifier(modifier, children)) => Item::Modifier(
+                modifier,
+                children
+                    .iter()
+                    .map(|child_id| self.copy_subtree(*child_id, transform))
+                    .collect(),
+            ),
+            Some(Item::Shape(shape, t)) => Item::Shape(shape, transform.compose(&t)),
+            Some(Item::Instance(component_id, t)) => {
+                Item::Instance(component_id, transform.compose(&t))
+            }
+            None => Item::Operator(Operator::Union, Vec::new()),
+        };
+        self.items.insert(copy_id, copy);
+        if let Some(mut properties) = self.properties.get(&id).cloned() {
+            // bindings and tracks of the placement were relative to the component, not to the copy
+            let placement = Transform::default().parameters();
+            let is_placement = |name: &String| placement.iter().any(|(n, _)| n == name);
+            properties.bindings.retain(|name, _| !is_placement(name));
+            properties.tracks.retain(|name, _| !is_placement(name));
+            self.properties.insert(copy_id, properties);
+        }
+        copy_id
+    }
+
+    /// Remove a component definition and every instance of it.
+    pub fn remove_component(&mut self, component_id: ComponentId) {
+        let Some(component) = self.components.remove(&component_id) else {
+            return;
+        };
+        let instances: Vec<ItemId> = self
+            .items
+            .iter()
+            .filter(|(_, item)| matches!(item, Item::Instance(c, _) if *c == component_id))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in instances {
+            self.remove_item(id);
+        }
+        self.remove_item(component.root_id);
+    }
+
+    pub fn properties(&self, id: ItemId) -> ItemProperties {
+        self.properties.get(&id).cloned().unwrap_or_default()
+    }
+
+    pub fn set_properties(&mut self, id: ItemId, properties: ItemProperties) {
+        self.properties.insert(id, properties);
+    }
+
+    /// Is the item or any operator containing it locked?
+    pub fn is_locked(&self, id: ItemId) -> bool {
+        let mut current_id = Some(id);
+        while let Some(id) = current_id {
+            if self.properties.get(&id).is_some_and(|p| p.locked) {
+                return true;
+            }
+            current_id = self.parent_id(id);
+        }
+        false
+    }
+
+    /// Human readable label of an item, falling back to its id.
+    pub fn label(&self, id: ItemId) -> String {
+        if let Some(properties) = self.properties.get(&id) {
+            if !properties.name.is_empty() {
+                return properties.name.clone();
+            }
+        }
+        match self.items.get(&id) {
+            Some(Item::Operator(op, _)) => format!("{op:?}"),
+            Some(Item::Lerp(_, _)) => "Lerp".to_string(),
+            Some(Item::Modifier(modifier, _)) => Into::<&str>::into(modifier).to_string(),
+            Some(Item::Shape(shape, _)) => Into::<&str>::into(shape).to_string(),
+            Some(Item::Instance(component_id, _)) => self
+                .components
+                .get(component_id)
+                .map_or_else(|| format!("{component_id:?}"), |c| c.name.clone()),
+            None => format!("{id:?}"),
+        }
+    }
+
+    /// Labels of the operators from the top of the tree down to the given item, followed by the
+    /// item itself.
+    pub fn path_labels(&self, id: ItemId) -> Vec<String> {
+        let mut path = vec![self.label(id)];
+        let mut current_id = id;
+        while let Some((parent_id, _)) = self.parent_and_pos(current_id) {
+            if parent_id == self.root_id {
+                break;
+            }
+            path.push(self.label(parent_id));
+            current_id = parent_id;
+        }
+        path.reverse();
+        path
+    }
+}
+
+//
+// Variables and parameters
+//
+impl Scene {
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+
+    pub fn set_variables(&mut self, variables: Vec<Variable>) {
+        self.variables = variables;
+    }
+
+    /// Values of the variables as of the last [Scene::apply_parameters]
+    pub fn variable_values(&self) -> &HashMap<String, f32> {
+        &self.variable_values
+    }
+
+    /// Errors of the last [Scene::apply_parameters]
+    pub fn parameter_errors(&self) -> &[String] {
+        &self.parameter_errors
+    }
+
+    /// Evaluate the scene variables, reporting unknown names and dependency cycles as errors.
+    ///
+    /// The timeline position is available as `time`, unless a variable has that name.
+    fn evaluate_variables(&self) -> (HashMap<String, f32>, Vec<String>) {
+        let expressions: HashMap<&str, Result<Expression, String>> = self
+            .variables
+            .iter()
+            .map(|v| (v.name.as_str(), Expression::parse(&v.expression)))
+            .collect();
+        let mut values = HashMap::new();
+        if !expressions.contains_key("time") {
+            values.insert("time".to_string(), self.timeline.time);
+        }
+        let mut errors = vec![];
+        for variable in &self.variables {
+            if let Err(error) =
+                evaluate_variable(&variable.name, &expressions, &mut values, &mut vec![])
+            {
+                errors.push(format!("{}: {error}", variable.name));
+            }
+        }
+        (values, errors)
+    }
+
+    /// Write the values of all animated and bound parameters into the items, at the current time
+    /// of the timeline. Bindings win over tracks.
+    pub fn apply_parameters(&mut self) {
+        let (values, mut errors) = self.evaluate_variables();
+        let mut binding_errors = vec![];
+        for (id, properties) in &self.properties {
+            let Some(item) = self.items.get_mut(id) else {
+                continue;
+            };
+            for (name, track) in &properties.tracks {
+                if let Some(value) = track.sample(self.timeline.time) {
+                    set_parameter(item, name, value);
+                }
+            }
+            for (name, source) in &properties.bindings {
+                match Expression::parse(source)
+                    .and_then(|expression| expression.eval(&|n| values.get(n).copied()))
+                {
+                    Ok(value) => set_parameter(item, name, value),
+                    Err(error) => binding_errors.push((*id, name.clone(), error)),
+                }
+            }
+        }
+        for (id, name, error) in binding_errors {
+            errors.push(format!("{}.{name}: {error}", self.label(id)));
+        }
+        self.variable_values = values;
+        self.parameter_errors = errors;
+    }
+
+    /// Parameters of the shape and placement of an item, which can be bound or animated
+    pub fn item_parameters(&self, id: ItemId) -> Vec<(&'static str, f32)> {
+        match self.items.get(&id) {
+            Some(Item::Shape(shape, transform)) => {
+                let mut parameters = shape.parameters();
+                parameters.extend(transform.parameters());
+                parameters
+            }
+            Some(Item::Instance(_, transform)) => transform.parameters(),
+            Some(Item::Lerp(t, _)) => vec![("t", *t)],
+            Some(Item::Modifier(modifier, _)) => modifier.parameters(),
+            _ => vec![],
+        }
+    }
+
+    /// Times of the keys of an item, sorted and without duplicates
+    pub fn key_times(&self, id: ItemId) -> Vec<f32> {
+        let mut times: Vec<f32> = self
+            .properties
+            .get(&id)
+            .into_iter()
+            .flat_map(|p| p.tracks.values())
+            .flat_map(|track| track.keys().iter().map(|key| key.time))
+            .collect();
+        times.sort_by(f32::total_cmp);
+        times.dedup();
+        times
+    }
+}
+
+/// Set parameter `name` of the shape or placement of an item.
+fn set_parameter(item: &mut Item, name: &str, value: f32) {
+    match item {
+        Item::Shape(shape, transform) => {
+            *shape = shape.set_parameter(name, value);
+            *transform = transform.set_parameter(name, value);
+        }
+        Item::Instance(_, transform) => {
+            *transform = transform.set_parameter(name, value);
+        }
+        Item::Lerp(t, _) => {
+            if name == "t" {
+                *t = value.clamp(0.0, 1.0);
+            }
+        }
+        Item::Modifier(modifier, _) => {
+            *modifier = modifier.set_parameter(name, value);
+        }
+        Item::Operator(_, _) => {}
+    }
+}
+
+/// Evaluate variable `name` and the variables it depends on, `stack` holding the variables
+/// being evaluated to detect cycles.
+fn evaluate_variable<'a>(
+    name: &'a str,
+    expressions: &'a HashMap<&'a str, Result<Expression, String>>,
+    values: &mut HashMap<String, f32>,
+    stack: &mut Vec<&'a str>,
+) -> Result<f32, String> {
+    if let Some(value) = values.get(name) {
+        return Ok(*value);
+    }
+    if let Some(start) = stack.iter().position(|n| *n == name) {
+        let cycle = stack[start..].iter().chain([&name]).join(" -> ");
+        return Err(format!("dependency cycle {cycle}"));
+    }
+    let expression = expressions
+        .get(name)
+        .ok_or_else(|| format!("unknown variable '{name}'"))?
+        .as_ref()
+        .map_err(Clone::clone)?;
+    stack.push(name);
+    for dependency in expression.variables() {
+        evaluate_variable(dependency, expressions, values, stack)?;
+    }
+    stack.pop();
+    let value = expression.eval(&|n| values.get(n).copied())?;
+    values.insert(name.to_string(), value);
+    Ok(value)
+}
+
+/// Placement of the items being generated as part of an instance
+#[derive(Clone, Copy)]
+struct InstanceContext<'a> {
+    /// Transform of the instance, applied on top of the items' own
+    transform: Transform,
+
+    /// Id of the outermost instance, reported instead of the items' ids so that picking selects it
+    id: ItemId,
+
+    /// Components being expanded, from the outermost, whose instances are left out to break the
+    /// cycles of scenes saved before they were rejected
+    components: &'a [ComponentId],
+}
+
+//
+// Instruction generation
+//
+impl Scene {
+    /// Compile the composition to the instructions evaluated by
+    /// [SdfInstructions](shared::sdf_interpreter::SdfInstructions), each shape reporting the id
+    /// of its item, or of the instance it is part of.
+    ///
+    /// Bound and animated parameters are as of the last [Scene::apply_parameters].
+    pub fn generate_instructions(&self) -> Vec<Instruction<SdfWrapper<Shape, ItemId>>> {
+        let mut instructions = Vec::with_capacity(self.items.len());
+        self.generate_instructions_for_id(&self.root_id, None, &mut instructions);
+        instructions
+    }
+
+    fn generate_instructions_for_id(
+        &self,
+        id: &ItemId,
+        instance: Option<InstanceContext<'_>>,
+        instructions: &mut Vec<Instruction<SdfWrapper<Shape, ItemId>>>,
+    ) -> bool {
+        if self.properties.get(id).is_some_and(|p| p.hidden) {
+            return false;
+        }
+        if let Some(item) = self.items.get(id) {
+            match item {
+                Item::Operator(op, ids) => {
+                    let mut items = ids.iter().rev();
+                    let mut r1 = false;
+                    while !r1 {
+                        if let Some(next_id) = items.next() {
+                            r1 = self.generate_instructions_for_id(next_id, instance, instructions);
+                        } else {
+                            return false;
+                        }
+                    }
+                    let mut r2 = false;
+                    while !r2 {
+                        if let Some(next_id) = items.next() {
+                            r2 = self.generate_instructions_for_id(next_id, instance, instructions);
+                        } else {
+                            return true;
+                        }
+                    }
+                    let op_to_add = if *op == Operator::Subtract {
+                        Operator::Union
+                    } else {
+                        *op
+                    };
+                    instructions.push(Instruction::Operator(op_to_add));
+                    for next_id in items {
+                        if self.generate_instructions_for_id(next_id, instance, instructions) {
+                            instructions.push(Instruction::Operator(op_to_add));
+                        }
+                    }
+                    if *op == Operator::Subtract {
+                        if let Some(Instruction::Operator(op)) = instructions.last_mut() {
+                            *op = Operator::Subtract;
+                        }
+                    }
+                    true
+                }
+                Item::Shape(shape, transform) => {
+                    let (transform, id) = match instance {
+                        Some(instance) => (instance.transform.compose(transform), instance.id),
+                        None => (*transform, *id),
+                    };
+                    instructions.push(Instruction::Sdf(SdfWrapper::new(*shape, id), transform));
+                    true
+                }
+                Item::Modifier(modifier, ids) => {
+                    let generated = ids.first().is_some_and(|id| {
+                        self.generate_instructions_for_id(id, instance, instructions)
+                    });
+                    if generated {
+                        instructions.push(Instruction::Modifier(*modifier));
+                    }
+                    generated
+                }
+                Item::Lerp(t, ids) => {
+                    let generated = ids
+                        .iter()
+                        .take(2)
+                        .filter(|id| self.generate_instructions_for_id(id, instance, instructions))
+                        .count();
+                    // with a child missing or hidden, the other one is used as is
+                    if generated == 2 {
+                        instructions.push(Instruction::Lerp(*t));
+                    }
+                    generated > 0
+                }
+                Item::Instance(component_id, transform) => {
+                    let Some(component) = self.components.get(component_id) else {
+                        return false;
+                    };
+                    let outer = instance.map_or(&[][..], |instance| instance.components);
+                    if outer.contains(component_id) {
+                        return false;
+                    }
+                    let components = [outer, &[*component_id]].concat();
+                    let instance = match instance {
+                        Some(instance) => InstanceContext {
+                            transform: instance.transform.compose(transform),
+                            id: instance.id,
+                            components: &components,
+                        },
+                        None => InstanceContext {
+                            transform: *transform,
+                            id: *id,
+                            components: &components,
+                        },
+                    };
+                    self.generate_instructions_for_id(
+                        &component.root_id,
+                        Some(instance),
+                        instructions,
+                    )
+                }
+            }
+        } else {
+            false
+        }
+    }
+}
+
+//
+// Files
+//
+impl Scene {
+    /// Write as JSON, with the items sorted by id so that saving the same scene twice gives the
+    /// same file
+    pub fn write<W: Write>(&self, w: W) -> io::Result<()> {
+        let file = SceneFile {
+            root_id: self.root_id,
+            items: self
+                .items
+                .iter()
+                .sorted_by_key(|(id, _)| id.0)
+                .map(|(id, item)| SceneItem {
+                    id: *id,
+                    item: item.clone(),
+                    properties: self.properties(*id),
+                })
+                .collect(),
+            components: self
+                .components
+                .iter()
+                .map(|(id, component)| (*id, component.clone()))
+                .collect(),
+            variables: self.variables.clone(),
+            timeline: self.timeline.clone(),
+        };
+        serde_json::to_writer_pretty(w, &file)?;
+        Ok(())
+    }
+
+    /// Read from JSON, as written by [Scene::write], failing with [io::ErrorKind::InvalidData]
+    /// unless the items form a valid scene
+    pub fn read<R: Read>(r: R) -> io::Result<Self> {
+        let file: SceneFile = serde_json::from_reader(r)?;
+        let mut scene = Self {
+            root_id: file.root_id,
+            components: file.components.into_iter().collect(),
+            variables: file.variables,
+            timeline: file.timeline,
+            ..Default::default()
+        };
+        scene.items.clear();
+        for SceneItem {
+            id,
+            item,
+            properties,
+        } in file.items
+        {
+            if scene.items.insert(id, item).is_some() {
+                return Err(invalid(format!("duplicate item {id:?}")));
+            }
+            if properties != ItemProperties::default() {
+                scene.properties.insert(id, properties);
+            }
+        }
+        scene.validate().map_err(invalid)?;
+        Ok(scene)
+    }
+
+    /// Check that the root and the roots of the components are unions of existing items, each of
+    /// which is the child of a single container, and that instances refer to components
+    fn validate(&self) -> Result<(), String> {
+        let roots = std::iter::once(("the root".to_string(), self.root_id)).chain(
+            self.components.iter().map(|(id, component)| {
+                (format!("the root of component {id:?}"), component.root_id)
+            }),
+        );
+        for (name, id) in roots.clone() {
+            match self.items.get(&id) {
+                Some(Item::Operator(_, _)) => {}
+                Some(_) => return Err(format!("{name} {id:?} is not an operator")),
+                None => return Err(format!("{name} {id:?} is missing")),
+            }
+        }
+        let mut parents = HashMap::new();
+        for (id, item) in &self.items {
+            if let Item::Instance(component_id, _) = item {
+                if !self.components.contains_key(component_id) {
+                    return Err(format!("item {id:?} is an instance of a missing component"));
+                }
+            }
+            let children = item.children().map_or(&[][..], Vec::as_slice);
+            if item
+                .capacity()
+                .is_some_and(|capacity| children.len() > capacity)
+            {
+                return Err(format!("item {id:?} has too many children"));
+            }
+            for child in children {
+                if !self.items.contains_key(child) {
+                    return Err(format!("child {child:?} of item {id:?} is missing"));
+                }
+                if parents.insert(*child, *id).is_some() {
+                    return Err(format!("item {child:?} has several parents"));
+                }
+            }
+        }
+        for (name, id) in roots {
+            if let Some(parent) = parents.get(&id) {
+                return Err(format!("{name} {id:?} is a child of item {parent:?}"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        self.write(io::BufWriter::new(std::fs::File::create(path)?))
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::read(io::BufReader::new(std::fs::File::open(path)?))
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -37,8 +37,8 @@ async fn run(options: Options, window: Window, compiled_shader_modules: Compiled
                             event_loop_window_target.exit()
                         }
                     }
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
+                    WindowEvent::CloseRequested => event_loop_window_target.exit(),
+                    WindowEvent::KeyboardInput {
                         event:
                             KeyEvent {
                                 state: ElementState::Pressed,
@@ -46,8 +46,11 @@ async fn run(options: Options, window: Window, compiled_shader_modules: Compiled
                                 ..
                             },
                         ..
-                    } => event_loop_window_target.exit(),
+                    } if !app.captures_escape() => event_loop_window_target.exit(),
                     WindowEvent::KeyboardInput { event, .. } => app.keyboard_input(event),
+                    WindowEvent::ModifiersChanged(modifiers) => {
+                        app.modifiers_changed(modifiers.state())
+                    }
                     WindowEvent::Resized(size) => app.resize(size),
                     WindowEvent::MouseInput { state, button, .. } => app.mouse_input(state, button),
                     WindowEvent::MouseWheel { delta, .. } => app.mouse_scroll(delta),
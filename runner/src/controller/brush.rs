@@ -0,0 +1,73 @@
+use dfutils::{
+    interval::Aabb,
+    polyline::simplify,
+    primitives::{CapsuleChain, MAX_POINTS},
+    primitives_enum::Shape,
+};
+use glam::*;
+use shared::sdf_interpreter::Transform;
+
+/// Points closer than this to the last one of the stroke are skipped, in world units
+const MIN_SPACING: f32 = 0.002;
+
+/// How far the simplified stroke may stray from the drawn one, relative to its radius
+const TOLERANCE: f32 = 0.25;
+
+/// Freehand drawing of strokes, which are added to the scene as capsule chains
+pub struct Brush {
+    /// Radius of the strokes, in world units
+    pub radius: f32,
+    /// Points of the stroke being drawn, in world space
+    stroke: Vec<Vec2>,
+    /// Whether the last stroke had more turns than a chain can hold, and was simplified further
+    /// than the tolerance
+    pub coarsened: bool,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            radius: 0.02,
+            stroke: vec![],
+            coarsened: false,
+        }
+    }
+}
+
+impl Brush {
+    pub fn stroke(&self) -> &[Vec2] {
+        &self.stroke
+    }
+
+    pub fn start(&mut self, position: Vec2) {
+        self.stroke = vec![position];
+    }
+
+    pub fn extend(&mut self, position: Vec2) {
+        if let Some(last) = self.stroke.last() {
+            if last.distance(position) > MIN_SPACING {
+                self.stroke.push(position);
+            }
+        }
+    }
+
+    /// Grow the radius by `factor`, or shrink it if it is less than 1
+    pub fn scale_radius(&mut self, factor: f32) {
+        self.radius = (self.radius * factor).clamp(0.001, 0.5);
+    }
+
+    /// The simplified stroke, centered on its transform, or `None` if no stroke was started
+    pub fn finish(&mut self) -> Option<(Shape, Transform)> {
+        let stroke = std::mem::take(&mut self.stroke);
+        if stroke.is_empty() {
+            return None;
+        }
+        let center = Aabb::from_points(&stroke).center();
+        let points: Vec<Vec2> = stroke.iter().map(|p| *p - center).collect();
+        let radii = vec![self.radius; points.len()];
+        let tolerance = TOLERANCE * self.radius;
+        self.coarsened = simplify(&points, tolerance).len() > MAX_POINTS;
+        let chain = CapsuleChain::from_stroke(&points, &radii, tolerance);
+        Some((chain.into(), Transform::from_position(center)))
+    }
+}
@@ -0,0 +1,192 @@
+use dfutils::primitives::*;
+use dfutils::primitives_enum::Shape;
+use glam::*;
+
+/// Length of the arrows used for direction parameters
+const ARROW_LENGTH: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum HandleStyle {
+    /// A knob controlling a size parameter
+    Knob,
+    /// A point of the shape which is dragged directly
+    Point,
+    /// The tip of an arrow starting at the origin of the shape
+    Arrow,
+}
+
+#[derive(Clone, Copy)]
+pub struct Handle {
+    /// Position in the local space of the shape
+    pub position: Vec2,
+    pub style: HandleStyle,
+}
+
+impl Handle {
+    const fn new(position: Vec2, style: HandleStyle) -> Self {
+        Self { position, style }
+    }
+}
+
+/// Draggable handles for the editable parameters of a shape, each tied to a single parameter
+pub trait Gizmos {
+    fn handles(&self) -> Vec<Handle>;
+
+    /// Update the parameter tied to handle `index` so that the handle ends up at `position`
+    fn drag_handle(self, index: usize, position: Vec2) -> Self;
+}
+
+impl Gizmos for Disk {
+    fn handles(&self) -> Vec<Handle> {
+        vec![Handle::new(vec2(self.radius, 0.0), HandleStyle::Knob)]
+    }
+
+    fn drag_handle(mut self, _index: usize, position: Vec2) -> Self {
+        self.radius = position.length();
+        self
+    }
+}
+
+impl Gizmos for Torus {
+    fn handles(&self) -> Vec<Handle> {
+        vec![
+            Handle::new(vec2(self.major_radius, 0.0), HandleStyle::Knob),
+            Handle::new(
+                vec2(0.0, self.major_radius + self.minor_radius),
+                HandleStyle::Knob,
+            ),
+        ]
+    }
+
+    fn drag_handle(mut self, index: usize, position: Vec2) -> Self {
+        if index == 0 {
+            self.major_radius = position.length();
+        } else {
+            self.minor_radius = (position.length() - self.major_radius).max(0.0);
+        }
+        self.minor_radius = self.minor_radius.min(self.major_radius);
+        self
+    }
+}
+
+impl Gizmos for Rectangle {
+    fn handles(&self) -> Vec<Handle> {
+        vec![
+            Handle::new(vec2(self.width * 0.5, 0.0), HandleStyle::Knob),
+            Handle::new(vec2(0.0, self.height * 0.5), HandleStyle::Knob),
+        ]
+    }
+
+    fn drag_handle(mut self, index: usize, position: Vec2) -> Self {
+        if index == 0 {
+            self.width = 2.0 * position.x.abs();
+        } else {
+            self.height = 2.0 * position.y.abs();
+        }
+        self
+    }
+}
+
+impl Gizmos for Cross {
+    fn handles(&self) -> Vec<Handle> {
+        vec![
+            Handle::new(vec2(self.length, 0.0), HandleStyle::Knob),
+            Handle::new(vec2(self.length, self.thickness), HandleStyle::Knob),
+        ]
+    }
+
+    fn drag_handle(mut self, index: usize, position: Vec2) -> Self {
+        if index == 0 {
+            self.length = position.x.abs();
+        } else {
+            self.thickness = position.y.abs();
+        }
+        self.thickness = self.thickness.min(self.length);
+        self
+    }
+}
+
+impl Gizmos for LineSegment {
+    fn handles(&self) -> Vec<Handle> {
+        vec![
+            Handle::new(self.a, HandleStyle::Point),
+            Handle::new(self.b, HandleStyle::Point),
+        ]
+    }
+
+    fn drag_handle(mut self, index: usize, position: Vec2) -> Self {
+        if index == 0 {
+            self.a = position;
+        } else {
+            self.b = position;
+        }
+        self
+    }
+}
+
+impl Gizmos for CapsuleChain {
+    fn handles(&self) -> Vec<Handle> {
+        self.points()
+            .iter()
+            .map(|p| Handle::new(*p, HandleStyle::Point))
+            .collect()
+    }
+
+    fn drag_handle(mut self, index: usize, position: Vec2) -> Self {
+        self.points[index] = position;
+        self
+    }
+}
+
+impl Gizmos for Plane {
+    fn handles(&self) -> Vec<Handle> {
+        vec![Handle::new(self.normal * ARROW_LENGTH, HandleStyle::Arrow)]
+    }
+
+    fn drag_handle(mut self, _index: usize, position: Vec2) -> Self {
+        self.normal = position.try_normalize().unwrap_or(self.normal);
+        self
+    }
+}
+
+impl Gizmos for Ray {
+    fn handles(&self) -> Vec<Handle> {
+        vec![Handle::new(
+            self.direction * ARROW_LENGTH,
+            HandleStyle::Arrow,
+        )]
+    }
+
+    fn drag_handle(mut self, _index: usize, position: Vec2) -> Self {
+        self.direction = position.try_normalize().unwrap_or(self.direction);
+        self
+    }
+}
+
+impl Gizmos for Shape {
+    fn handles(&self) -> Vec<Handle> {
+        match self {
+            Shape::Disk(shape) => shape.handles(),
+            Shape::Torus(shape) => shape.handles(),
+            Shape::Rectangle(shape) => shape.handles(),
+            Shape::Cross(shape) => shape.handles(),
+            Shape::LineSegment(shape) => shape.handles(),
+            Shape::CapsuleChain(shape) => shape.handles(),
+            Shape::Plane(shape) => shape.handles(),
+            Shape::Ray(shape) => shape.handles(),
+        }
+    }
+
+    fn drag_handle(self, index: usize, position: Vec2) -> Self {
+        match self {
+            Shape::Disk(shape) => shape.drag_handle(index, position).into(),
+            Shape::Torus(shape) => shape.drag_handle(index, position).into(),
+            Shape::Rectangle(shape) => shape.drag_handle(index, position).into(),
+            Shape::Cross(shape) => shape.drag_handle(index, position).into(),
+            Shape::LineSegment(shape) => shape.drag_handle(index, position).into(),
+            Shape::CapsuleChain(shape) => shape.drag_handle(index, position).into(),
+            Shape::Plane(shape) => shape.drag_handle(index, position).into(),
+            Shape::Ray(shape) => shape.drag_handle(index, position).into(),
+        }
+    }
+}
@@ -60,18 +60,8 @@ pub fn generate_icons() -> IconImages {
 fn get_instructions(op: Operator) -> [Instruction<Shape>; 3] {
     let disk = Shape::Disk(Disk::new(0.25));
     [
-        Instruction::Sdf(
-            disk,
-            Transform {
-                position: vec2(0.1, 0.0),
-            },
-        ),
-        Instruction::Sdf(
-            disk,
-            Transform {
-                position: vec2(-0.1, 0.0),
-            },
-        ),
+        Instruction::Sdf(disk, Transform::from_position(vec2(0.1, 0.0))),
+        Instruction::Sdf(disk, Transform::from_position(vec2(-0.1, 0.0))),
         Instruction::Operator(op),
     ]
 }
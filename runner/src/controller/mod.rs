@@ -2,31 +2,68 @@ use crate::{
     bind_group_buffer::{BindGroupBufferType, BufferData, SSBO},
     window::UserEvent,
 };
+use brush::Brush;
 use bytemuck::Zeroable;
-use dfutils::{grid::*, primitives_enum::Shape, sdf::Sdf};
+use dfutils::{grid::*, primitives::MAX_POINTS, primitives_enum::Shape, sdf::Sdf};
 use egui::{Context, CursorIcon};
 use egui_winit::winit::{
     dpi::{PhysicalPosition, PhysicalSize},
     event::{ElementState, KeyEvent, MouseButton},
     event_loop::EventLoopProxy,
-    keyboard::{Key, NamedKey},
+    keyboard::{Key, ModifiersState, NamedKey},
 };
+use gizmos::{Gizmos, HandleStyle};
 use glam::*;
 use icons::TextureHandles;
+use preview::Preview;
 use resize::Resize;
-use sdf_builder_tree::{Command, Item, ItemId, SdfBuilderTree, SelectedItem};
+use sdf_builder_tree::{Command, SdfBuilderTree, SelectedItem};
+use sdf_scene::{Item, ItemId};
 use shared::{
     from_pixels,
     push_constants::sdf_builder::ShaderConstants,
     sdf_interpreter::{Instruction, SdfInstructions, Transform},
     sdf_wrapper::{SdfWrapper, WrappedDistance},
+    to_pixels,
 };
-use std::time::{Duration, Instant};
+use std::{
+    path::{Path, PathBuf},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+use transform_mode::{TransformKind, TransformMode};
 
+mod brush;
+mod gizmos;
 mod icons;
+mod modifier_ui;
+mod preview;
 mod resize;
+mod scale;
 mod sdf_builder_tree;
 pub mod shape_ui;
+mod transform_mode;
+
+/// Mesh the scene saved at `scene` extruded along Z, and write it to `output` as STL or OBJ.
+/// Returns the number of triangles.
+pub fn export_mesh(
+    scene: &Path,
+    output: &Path,
+    height: f32,
+    rounding: f32,
+    half_size: Vec2,
+    cell_size: f32,
+) -> std::io::Result<usize> {
+    let mut sdf_builder_tree = SdfBuilderTree::default();
+    sdf_builder_tree.load(scene)?;
+    sdf_builder_tree.export_mesh(output, height, rounding, half_size, cell_size)
+}
+
+/// How close to a shape's boundary, in pixels, a click has to be to pick it.
+const PICK_RADIUS: f32 = 4.0;
+
+/// Radius of the gizmo handles in world space.
+const HANDLE_RADIUS: f32 = 0.01;
 
 #[derive(Clone, Copy)]
 struct Grabbing {
@@ -43,10 +80,11 @@ impl Grabbing {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 enum GrabType {
     Move,
     Resize,
+    Handle(usize),
     None,
 }
 
@@ -58,7 +96,8 @@ impl GrabType {
 
 pub struct Controller {
     size: PhysicalSize<u32>,
-    start: Instant,
+    /// When the timeline was last advanced
+    last_frame: Instant,
     shader_constants: ShaderConstants,
     grid: Grid<WrappedDistance<ItemId>>,
     sdf_builder_tree: SdfBuilderTree,
@@ -70,6 +109,16 @@ pub struct Controller {
     texture_handles: TextureHandles,
     instructions: Vec<Instruction<SdfWrapper<Shape, ItemId>>>,
     last_mouse_press: (Vec2, std::time::Instant),
+    last_pick: Option<Vec2>,
+    modifiers: ModifiersState,
+    transform_mode: Option<TransformMode>,
+    /// Strokes drawn on the canvas become new shapes while this is set
+    brush: Option<Brush>,
+    preview: Preview,
+    /// Mesh being written in the background, with its number of triangles
+    mesh_export: Option<JoinHandle<std::io::Result<usize>>>,
+    /// Why the last save or load of the scene failed, shown until the next one succeeds
+    file_error: Option<String>,
 }
 
 impl Controller {
@@ -77,7 +126,7 @@ impl Controller {
         let now = Instant::now();
         Self {
             size,
-            start: now,
+            last_frame: now,
             shader_constants: ShaderConstants::zeroed(),
             grid: Grid::new(size.width as usize, size.height as usize),
             sdf_builder_tree: SdfBuilderTree::default(),
@@ -89,6 +138,13 @@ impl Controller {
             texture_handles: TextureHandles::empty(),
             instructions: vec![],
             last_mouse_press: (Vec2::ZERO, now),
+            last_pick: None,
+            modifiers: ModifiersState::empty(),
+            transform_mode: None,
+            brush: None,
+            preview: Preview::default(),
+            mesh_export: None,
+            file_error: None,
         }
     }
 
@@ -100,8 +156,23 @@ impl Controller {
     }
 
     pub fn mouse_move(&mut self, position: PhysicalPosition<f64>) {
+        let previous = self.cursor_from_pixels();
         self.cursor = vec2(position.x as f32, position.y as f32);
         let cursor = self.cursor_from_pixels();
+        if self.preview.is_3d() {
+            if self.mouse_button_pressed {
+                self.preview.orbit(cursor - previous);
+            }
+            return;
+        }
+        if self.transform_mode.is_some() {
+            self.preview_transform();
+            return;
+        }
+        if let Some(brush) = &mut self.brush {
+            brush.extend(cursor);
+            return;
+        }
         if let (
             Some(Grabbing {
                 position,
@@ -120,21 +191,38 @@ impl Controller {
                         *shape,
                         Transform {
                             position: transform.position - (position - cursor),
+                            ..*transform
+                        },
+                    ),
+                    Item::Instance(component_id, transform) => Item::Instance(
+                        *component_id,
+                        Transform {
+                            position: transform.position - (position - cursor),
+                            ..*transform
                         },
                     ),
-                    Item::Operator(_, _) => todo!(),
+                    // operators have no transform of their own to move
+                    Item::Operator(_, _) | Item::Lerp(_, _) | Item::Modifier(_, _) => return,
                 },
                 GrabType::Resize => match item {
                     Item::Shape(shape, transform) => Item::Shape(
                         shape.resize(
-                            position - transform.position,
-                            cursor - transform.position,
+                            transform.to_local(position),
+                            transform.to_local(cursor),
                             derivative,
                         ),
                         *transform,
                     ),
                     _ => todo!(),
                 },
+                GrabType::Handle(index) => match item {
+                    Item::Shape(shape, transform) => Item::Shape(
+                        shape.drag_handle(index, transform.to_local(cursor)),
+                        *transform,
+                    ),
+                    // only shapes have handles
+                    _ => return,
+                },
                 GrabType::None => unimplemented!(),
             };
             self.sdf_builder_tree
@@ -143,6 +231,36 @@ impl Controller {
     }
 
     pub fn mouse_input(&mut self, state: ElementState, button: MouseButton) {
+        if self.transform_mode.is_some() {
+            if state.is_pressed() {
+                match button {
+                    MouseButton::Left => self.transform_mode = None,
+                    MouseButton::Right => self.cancel_transform(),
+                    _ => {}
+                }
+            }
+            return;
+        }
+        if self.preview.is_3d() {
+            if button == MouseButton::Left {
+                self.mouse_button_pressed = state.is_pressed();
+            }
+            return;
+        }
+        let cursor = self.cursor_from_pixels();
+        if let Some(brush) = &mut self.brush {
+            if button == MouseButton::Left {
+                if state.is_pressed() {
+                    brush.start(cursor);
+                } else if let Some((shape, transform)) = brush.finish() {
+                    self.sdf_builder_tree.send_command(Command::CombineItem {
+                        item: Item::Shape(shape, transform),
+                        item_id: ItemId::random(),
+                    });
+                }
+            }
+            return;
+        }
         if button == MouseButton::Left {
             self.mouse_button_pressed = match state {
                 ElementState::Pressed => {
@@ -164,15 +282,14 @@ impl Controller {
                     if press_position.distance_squared(self.cursor) < 4.0
                         && instant.elapsed() < Duration::from_millis(300)
                     {
-                        let to_select = self.get_item_for_selection();
-                        let selected_item = &self.sdf_builder_tree.selected_item;
-                        self.sdf_builder_tree.send_command(Command::SetSelectedItem(
-                            if to_select.id == selected_item.id {
-                                SelectedItem::NONE
-                            } else {
-                                to_select
-                            },
-                        ));
+                        let to_select = if self.modifiers.alt_key() {
+                            self.get_parent_for_selection()
+                        } else {
+                            self.get_item_for_selection()
+                        };
+                        self.sdf_builder_tree
+                            .send_command(Command::SetSelectedItem(to_select));
+                        self.last_pick = Some(self.cursor);
                     }
                     false
                 }
@@ -180,23 +297,69 @@ impl Controller {
         }
     }
 
+    pub fn modifiers_changed(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
     pub fn keyboard_input(&mut self, key: KeyEvent) {
         if !key.state.is_pressed() {
             return;
         }
-        if let Key::Named(NamedKey::Delete) = key.logical_key {
-            if let Some(item_id) = self.sdf_builder_tree.selected_item.id {
-                self.sdf_builder_tree
-                    .send_command(Command::RemoveItem { item_id });
-                self.sdf_builder_tree.selected_item = SelectedItem::NONE;
+        if let Some(transform_mode) = &mut self.transform_mode {
+            match key.logical_key {
+                Key::Named(NamedKey::Enter) => self.transform_mode = None,
+                Key::Named(NamedKey::Escape) => self.cancel_transform(),
+                ref key => {
+                    if transform_mode.key_input(key) {
+                        self.preview_transform();
+                    }
+                }
+            }
+            return;
+        }
+        if let Some(brush) = &mut self.brush {
+            match key.logical_key {
+                Key::Named(NamedKey::Escape) => self.brush = None,
+                Key::Character(ref c) => match c.to_lowercase().as_str() {
+                    "b" => self.brush = None,
+                    "[" => brush.scale_radius(0.8),
+                    "]" => brush.scale_radius(1.25),
+                    _ => {}
+                },
+                _ => {}
+            }
+            return;
+        }
+        match key.logical_key {
+            Key::Named(NamedKey::Delete) => {
+                if let Some(item_id) = self.sdf_builder_tree.selected_item.id {
+                    self.sdf_builder_tree
+                        .send_command(Command::RemoveItem { item_id });
+                    self.sdf_builder_tree.selected_item = SelectedItem::NONE;
+                }
+            }
+            Key::Character(ref c) if !self.modifiers.control_key() => {
+                match c.to_lowercase().as_str() {
+                    "g" => self.start_transform(TransformKind::Grab),
+                    "s" => self.start_transform(TransformKind::Scale),
+                    "r" => self.start_transform(TransformKind::Rotate),
+                    "b" if !self.preview.is_3d() => self.brush = Some(Brush::default()),
+                    _ => {}
+                }
             }
+            _ => {}
         }
     }
 
+    /// Whether Escape is needed by the controller rather than quitting the app
+    pub fn captures_escape(&self) -> bool {
+        self.transform_mode.is_some() || self.brush.is_some()
+    }
+
     pub fn update(&mut self) {
         self.shader_constants = ShaderConstants {
             size: self.size.into(),
-            time: self.start.elapsed().as_secs_f32(),
+            time: self.sdf_builder_tree.scene.timeline.time,
             mouse_button_pressed: (self.mouse_button_pressed && self.grabbing.is_none()).into(),
             cursor: self.cursor_from_pixels().into(),
             selected_id: self
@@ -205,6 +368,12 @@ impl Controller {
                 .id
                 .map(|id| id.0)
                 .unwrap_or(0),
+            preview: self.preview.mode.into(),
+            preview_size: self.preview.size(),
+            rounding: self.preview.rounding,
+            camera_yaw: self.preview.camera.yaw,
+            camera_pitch: self.preview.camera.pitch,
+            camera_distance: self.preview.camera.distance,
         }
     }
 
@@ -219,9 +388,35 @@ impl Controller {
         event_proxy: &EventLoopProxy<UserEvent>,
     ) {
         self.init_icon_textures(ctx);
-        if self.grabbing.is_some() {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+        if self.sdf_builder_tree.scene.timeline.advance(dt) {
+            self.sdf_builder_tree.grid_needs_updating = true;
+        }
+        if let Some(transform_mode) = &self.transform_mode {
+            egui::Area::new("transform_mode".into())
+                .pivot(egui::Align2::LEFT_BOTTOM)
+                .fixed_pos(ctx.available_rect().left_bottom() + egui::vec2(8.0, -8.0))
+                .interactable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "{}    Enter: confirm, Esc: cancel, X/Y: constrain",
+                        transform_mode.status()
+                    ));
+                });
+        } else if let Some(brush) = &self.brush {
+            self.grab_type = GrabType::None;
+            ctx.set_cursor_icon(CursorIcon::Crosshair);
+            self.brush_ui(ctx, brush);
+        } else if self.preview.is_3d() {
+            self.grab_type = GrabType::None;
+            if self.mouse_button_pressed {
+                ctx.set_cursor_icon(CursorIcon::Grabbing);
+            }
+        } else if self.grabbing.is_some() {
             match self.grab_type {
-                GrabType::Move => {
+                GrabType::Move | GrabType::Handle(_) => {
                     ctx.set_cursor_icon(CursorIcon::Grabbing);
                 }
                 GrabType::Resize => {
@@ -229,15 +424,25 @@ impl Controller {
                 }
                 GrabType::None => {}
             }
-        } else if let Some(item) = &self.sdf_builder_tree.get_selected_item() {
-            if let Item::Shape(shape, transform) = item {
-                self.set_grab_type(ctx, *shape, self.cursor_from_pixels() - transform.position);
+        } else if let (Some(item), false) = (
+            &self.sdf_builder_tree.get_selected_item(),
+            self.selected_item_is_locked(),
+        ) {
+            match item {
+                Item::Shape(shape, transform) => {
+                    self.set_grab_type(ctx, *shape, transform.to_local(self.cursor_from_pixels()));
+                }
+                Item::Instance(_, _) => self.set_instance_grab_type(ctx),
+                Item::Operator(_, _) | Item::Lerp(_, _) | Item::Modifier(_, _) => {}
             }
         } else {
             self.grab_type = GrabType::None;
             ctx.set_cursor_icon(CursorIcon::Default);
         }
 
+        if let Some(error) = &self.file_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
         self.sdf_builder_tree
             .ui(ui, &self.texture_handles, self.size);
         if self.sdf_builder_tree.grid_needs_updating {
@@ -250,6 +455,204 @@ impl Controller {
         }
     }
 
+    /// Overlay next to the cursor showing what the composition evaluates to at that point.
+    pub fn inspector_ui(&self, ctx: &Context) {
+        if ctx.is_pointer_over_area() || self.instructions.is_empty() || self.preview.is_3d() {
+            return;
+        }
+        let Some(pointer) = ctx.pointer_hover_pos() else {
+            return;
+        };
+        let p = self.cursor_from_pixels();
+        let sdf = SdfInstructions::new(&self.instructions);
+        let tracked = sdf.signed_distance_tracked(p);
+        let WrappedDistance { d, data: item_id } = tracked.distance;
+        let gradient = sdf.derivative(p, 0.001);
+        let path = self.sdf_builder_tree.path_labels(item_id);
+
+        let radius = d.abs() * self.size.height as f32 / ctx.pixels_per_point();
+        ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            "inspector_circle".into(),
+        ))
+        .circle_stroke(pointer, radius, (1.0, egui::Color32::from_white_alpha(128)));
+
+        egui::Area::new("inspector".into())
+            .order(egui::Order::Tooltip)
+            .fixed_pos(pointer + egui::vec2(16.0, 16.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    egui::Grid::new("inspector_grid").show(ui, |ui| {
+                        ui.label("pos");
+                        ui.label(format!("{:.3}, {:.3}", p.x, p.y));
+                        ui.end_row();
+                        ui.label("distance");
+                        if tracked.exact {
+                            ui.label(format!("{d:.4}"));
+                        } else {
+                            ui.label(format!("{d:.4} (bound)"))
+                                .on_hover_text("Only a lower bound of the distance to the contour");
+                        }
+                        ui.end_row();
+                        ui.label("gradient");
+                        ui.label(format!("{:.3}, {:.3}", gradient.x, gradient.y));
+                        ui.end_row();
+                        ui.label("item");
+                        ui.label(format!("{item_id:?}"));
+                        ui.end_row();
+                        ui.label("path");
+                        ui.label(path.join(" > "));
+                        ui.end_row();
+                    });
+                });
+            });
+    }
+
+    /// Handles for the parameters of the selected shape, drawn over the canvas.
+    pub fn gizmos_ui(&self, ctx: &Context) {
+        let Some(Item::Shape(shape, transform)) = self.sdf_builder_tree.get_selected_item() else {
+            return;
+        };
+        if self.selected_item_is_locked() || self.preview.is_3d() {
+            return;
+        }
+        let painter = ctx
+            .layer_painter(egui::LayerId::new(egui::Order::Foreground, "gizmos".into()))
+            .with_clip_rect(ctx.available_rect());
+        let pixels_per_point = ctx.pixels_per_point();
+        let to_screen = |p: Vec2| {
+            let p = to_pixels(transform.from_local(p), self.size.into()) / pixels_per_point;
+            egui::pos2(p.x, p.y)
+        };
+        let origin = to_screen(Vec2::ZERO);
+        let radius = HANDLE_RADIUS * self.size.height as f32 / pixels_per_point;
+        for (index, handle) in shape.handles().into_iter().enumerate() {
+            let color = if self.grab_type == GrabType::Handle(index) {
+                egui::Color32::YELLOW
+            } else {
+                egui::Color32::WHITE
+            };
+            let stroke = egui::Stroke::new(1.5, egui::Color32::BLACK);
+            let position = to_screen(handle.position);
+            match handle.style {
+                HandleStyle::Knob => {
+                    painter.circle(position, radius * 0.7, color, stroke);
+                }
+                HandleStyle::Point => {
+                    painter.rect(
+                        egui::Rect::from_center_size(position, egui::Vec2::splat(radius * 1.2)),
+                        0.0,
+                        color,
+                        stroke,
+                    );
+                }
+                HandleStyle::Arrow => {
+                    painter.arrow(origin, position - origin, (2.0, color));
+                    painter.circle(position, radius * 0.5, color, stroke);
+                }
+            }
+        }
+    }
+
+    /// Status of the brush mode, and the stroke being drawn with the outline of the brush
+    fn brush_ui(&self, ctx: &Context, brush: &Brush) {
+        egui::Area::new("brush".into())
+            .pivot(egui::Align2::LEFT_BOTTOM)
+            .fixed_pos(ctx.available_rect().left_bottom() + egui::vec2(8.0, -8.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                if brush.coarsened {
+                    ui.label(format!(
+                        "The last stroke was simplified to fit in {MAX_POINTS} points, \
+                        draw long strokes in several parts to keep their details"
+                    ));
+                }
+                ui.label(format!(
+                    "Brush radius {:.3}    Drag: draw, [/]: radius, B/Esc: exit",
+                    brush.radius
+                ));
+            });
+        let painter = ctx
+            .layer_painter(egui::LayerId::new(egui::Order::Foreground, "brush".into()))
+            .with_clip_rect(ctx.available_rect());
+        let pixels_per_point = ctx.pixels_per_point();
+        let to_screen = |p: Vec2| {
+            let p = to_pixels(p, self.size.into()) / pixels_per_point;
+            egui::pos2(p.x, p.y)
+        };
+        let radius = brush.radius * self.size.height as f32 / pixels_per_point;
+        let color = egui::Color32::from_white_alpha(96);
+        painter.add(egui::Shape::line(
+            brush.stroke().iter().map(|p| to_screen(*p)).collect(),
+            egui::Stroke::new(2.0 * radius, color),
+        ));
+        painter.circle_stroke(
+            to_screen(self.cursor_from_pixels()),
+            radius,
+            (1.0, egui::Color32::WHITE),
+        );
+    }
+
+    pub fn preview_ui(&mut self, ui: &mut egui::Ui) {
+        if self
+            .mesh_export
+            .as_ref()
+            .is_some_and(|export| export.is_finished())
+        {
+            let path = &self.preview.mesh_path;
+            match self.mesh_export.take().map(JoinHandle::join) {
+                Some(Ok(Ok(triangles))) => println!("Wrote {triangles} triangles to {path}"),
+                Some(Ok(Err(e))) => eprintln!("Failed to export mesh to {path}: {e}"),
+                Some(Err(_)) => eprintln!("Failed to export mesh to {path}: meshing panicked"),
+                None => {}
+            }
+        }
+        if self.preview.ui(ui, self.mesh_export.is_some()) {
+            let size = self.size;
+            let half_size = vec2(0.5 * size.width as f32 / size.height as f32, 0.5);
+            let instructions = self.instructions.clone();
+            let path = PathBuf::from(&self.preview.mesh_path);
+            let Preview {
+                height,
+                rounding,
+                cell_size,
+                ..
+            } = self.preview;
+            // meshing the whole view takes a while, which would freeze the ui
+            self.mesh_export = Some(std::thread::spawn(move || {
+                sdf_builder_tree::write_mesh(
+                    &instructions,
+                    &path,
+                    height,
+                    rounding,
+                    half_size,
+                    cell_size,
+                )
+            }));
+        }
+    }
+
+    pub fn timeline_ui(&mut self, ui: &mut egui::Ui) {
+        self.sdf_builder_tree.timeline_ui(ui);
+    }
+
+    pub fn save_scene(&mut self, path: &str) {
+        self.file_error = self
+            .sdf_builder_tree
+            .save(Path::new(path))
+            .err()
+            .map(|e| format!("Failed to save scene to {path}: {e}"));
+    }
+
+    pub fn load_scene(&mut self, path: &str) {
+        self.file_error = self
+            .sdf_builder_tree
+            .load(Path::new(path))
+            .err()
+            .map(|e| format!("Failed to load scene from {path}: {e}"));
+    }
+
     pub fn buffers(&self) -> BufferData {
         BufferData {
             bind_group_buffers: vec![BindGroupBufferType::SSBO(SSBO {
@@ -265,11 +668,56 @@ impl Controller {
         from_pixels(self.cursor, self.size.into())
     }
 
+    fn selected_item_is_locked(&self) -> bool {
+        self.sdf_builder_tree
+            .selected_item
+            .id
+            .is_some_and(|id| self.sdf_builder_tree.scene.is_locked(id))
+    }
+
+    fn start_transform(&mut self, kind: TransformKind) {
+        if self.selected_item_is_locked() || self.preview.is_3d() {
+            return;
+        }
+        if let (Some(item_id), Some(Item::Shape(shape, transform))) = (
+            self.sdf_builder_tree.selected_item.id,
+            self.sdf_builder_tree.get_selected_item(),
+        ) {
+            self.transform_mode = Some(TransformMode::new(
+                kind,
+                item_id,
+                *shape,
+                *transform,
+                self.cursor_from_pixels(),
+            ));
+        }
+    }
+
+    fn preview_transform(&self) {
+        if let Some(transform_mode) = &self.transform_mode {
+            let (shape, transform) = transform_mode.apply(self.cursor_from_pixels());
+            self.sdf_builder_tree.send_command(Command::EditItem {
+                item: Item::Shape(shape, transform),
+                item_id: transform_mode.item_id,
+            });
+        }
+    }
+
+    fn cancel_transform(&mut self) {
+        if let Some(transform_mode) = self.transform_mode.take() {
+            let (shape, transform) = transform_mode.original;
+            self.sdf_builder_tree.send_command(Command::EditItem {
+                item: Item::Shape(shape, transform),
+                item_id: transform_mode.item_id,
+            });
+        }
+    }
+
     fn derivative_at_cursor(&self) -> Vec2 {
         if let Some(item) = &self.sdf_builder_tree.get_selected_item() {
             match item {
                 Item::Shape(shape, transform) => {
-                    shape.derivative(self.cursor_from_pixels() - transform.position, 0.01)
+                    shape.derivative(transform.to_local(self.cursor_from_pixels()), 0.01)
                 }
                 _ => Vec2::ZERO,
             }
@@ -284,6 +732,10 @@ impl Controller {
             self.derivative_at_cursor(),
             |Grabbing { derivative, .. }| derivative,
         );
+        let d = match self.sdf_builder_tree.get_selected_item() {
+            Some(Item::Shape(_, transform)) => Vec2::from_angle(transform.rotation).rotate(d),
+            _ => d,
+        };
         let slope = d.y / d.x;
         if slope > 1.0 / H && slope < H {
             CursorIcon::ResizeNeSw
@@ -314,7 +766,29 @@ impl Controller {
         }
     }
 
+    /// Instances can only be moved, by grabbing them inside their outline
+    fn set_instance_grab_type(&mut self, ctx: &Context) {
+        let winner =
+            SdfInstructions::new(&self.instructions).signed_distance(self.cursor_from_pixels());
+        self.grab_type =
+            if winner.d < 0.0 && Some(winner.data) == self.sdf_builder_tree.selected_item.id {
+                ctx.set_cursor_icon(CursorIcon::Grab);
+                GrabType::Move
+            } else {
+                GrabType::None
+            };
+    }
+
     fn set_grab_type(&mut self, ctx: &Context, shape: Shape, position: Vec2) {
+        if let Some(index) = shape
+            .handles()
+            .iter()
+            .position(|handle| handle.position.distance(position) < HANDLE_RADIUS)
+        {
+            ctx.set_cursor_icon(CursorIcon::Grab);
+            self.grab_type = GrabType::Handle(index);
+            return;
+        }
         let d = shape.signed_distance(position);
         self.grab_type = match shape {
             Shape::LineSegment(line_segment) => {
@@ -377,12 +851,62 @@ impl Controller {
         }
     }
 
+    /// Items under the cursor: the one winning the composition first, followed by every leaf
+    /// close enough to the cursor, including occluded and subtracted ones.
+    fn pick_candidates(&self) -> Vec<ItemId> {
+        let p = self.cursor_from_pixels();
+        let mut candidates = vec![];
+        let winner = self.grid.signed_distance(p);
+        if winner.d != f32::INFINITY {
+            candidates.push(winner.data);
+        }
+        let threshold = PICK_RADIUS / self.size.height as f32;
+        for candidate in SdfInstructions::new(&self.instructions).pick(p, threshold) {
+            let id = candidate.data;
+            if !candidates.contains(&id) {
+                candidates.push(id);
+            }
+        }
+        candidates.retain(|id| !self.sdf_builder_tree.scene.is_locked(*id));
+        candidates
+    }
+
+    /// Clicking repeatedly at the same spot cycles through the candidates and then deselects.
     fn get_item_for_selection(&self) -> SelectedItem {
-        let wrapped_distance = self.grid.signed_distance(self.cursor_from_pixels());
-        if wrapped_distance.d == f32::INFINITY {
-            SelectedItem::NONE
-        } else {
-            wrapped_distance.data.into()
+        let candidates = self.pick_candidates();
+        let selected_index = self
+            .sdf_builder_tree
+            .selected_item
+            .id
+            .and_then(|id| candidates.iter().position(|candidate| *candidate == id));
+        let repeated = self
+            .last_pick
+            .is_some_and(|position| position.distance_squared(self.cursor) < 4.0);
+        match selected_index {
+            Some(i) if repeated => candidates
+                .get(i + 1)
+                .map_or(SelectedItem::NONE, |id| (*id).into()),
+            Some(0) => SelectedItem::NONE,
+            _ => candidates
+                .first()
+                .map_or(SelectedItem::NONE, |id| (*id).into()),
         }
     }
+
+    /// The operator containing the selected item if it is under the cursor, otherwise the one
+    /// containing the item winning at the cursor.
+    fn get_parent_for_selection(&self) -> SelectedItem {
+        let candidates = self.pick_candidates();
+        let tree = &self.sdf_builder_tree;
+        tree.selected_item
+            .id
+            .filter(|id| {
+                candidates
+                    .iter()
+                    .any(|candidate| tree.contains(*id, *candidate) || id == candidate)
+            })
+            .or(candidates.first().copied())
+            .and_then(|id| tree.parent_id(id))
+            .map_or(SelectedItem::NONE, Into::into)
+    }
 }
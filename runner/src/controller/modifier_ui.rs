@@ -0,0 +1,78 @@
+use dfutils::{noise::*, outline::Outline};
+use shared::sdf_interpreter::Modifier;
+use strum::IntoEnumIterator;
+
+/// The ui for the parameters of modifier nodes of [SdfBuilderTree]
+pub trait ModifierUi {
+    fn ui(self, ui: &mut egui::Ui) -> Self;
+}
+
+impl ModifierUi for Noise {
+    fn ui(mut self, ui: &mut egui::Ui) -> Self {
+        ui.label("Noise");
+        egui::ComboBox::from_id_source("noise_kind")
+            .selected_text(Into::<&str>::into(self.kind))
+            .show_ui(ui, |ui| {
+                for kind in NoiseKind::iter() {
+                    let label: &str = kind.into();
+                    ui.selectable_value(&mut self.kind, kind, label);
+                }
+            });
+        ui.end_row();
+        ui.label("Frequency");
+        ui.add(
+            egui::DragValue::new(&mut self.frequency)
+                .range(0.0..=f64::INFINITY)
+                .speed(0.1),
+        );
+        ui.end_row();
+        ui.label("Amplitude");
+        ui.add(egui::DragValue::new(&mut self.amplitude).speed(0.001));
+        ui.end_row();
+        ui.label("Octaves");
+        ui.add(egui::DragValue::new(&mut self.octaves).range(1..=8));
+        ui.end_row();
+        ui.label("Seed");
+        ui.add(egui::DragValue::new(&mut self.seed));
+        self
+    }
+}
+
+impl ModifierUi for Outline {
+    fn ui(mut self, ui: &mut egui::Ui) -> Self {
+        ui.label("Offset");
+        ui.add(egui::DragValue::new(&mut self.offset).speed(0.001));
+        ui.end_row();
+        ui.label("Width");
+        ui.add(
+            egui::DragValue::new(&mut self.width)
+                .range(0.0..=f64::INFINITY)
+                .speed(0.001),
+        );
+        ui.end_row();
+        ui.label("Dash");
+        ui.add(
+            egui::DragValue::new(&mut self.dash)
+                .range(0.0..=f64::INFINITY)
+                .speed(0.001),
+        )
+        .on_hover_text("0 for a solid outline");
+        ui.end_row();
+        ui.label("Gap");
+        ui.add(
+            egui::DragValue::new(&mut self.gap)
+                .range(0.0..=f64::INFINITY)
+                .speed(0.001),
+        );
+        self
+    }
+}
+
+impl ModifierUi for Modifier {
+    fn ui(self, ui: &mut egui::Ui) -> Self {
+        match self {
+            Modifier::Displace(noise) => Modifier::Displace(noise.ui(ui)),
+            Modifier::Outline(outline) => Modifier::Outline(outline.ui(ui)),
+        }
+    }
+}
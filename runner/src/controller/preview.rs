@@ -0,0 +1,135 @@
+use dfutils::sdf3::Camera;
+use glam::Vec2;
+use shared::push_constants::sdf_builder::preview;
+use strum::IntoEnumIterator;
+
+#[derive(Clone, Copy, PartialEq, strum::EnumIter, strum::IntoStaticStr)]
+pub enum PreviewMode {
+    Flat,
+    Extrude,
+    Revolve,
+}
+
+impl From<PreviewMode> for u32 {
+    fn from(mode: PreviewMode) -> Self {
+        match mode {
+            PreviewMode::Flat => preview::FLAT,
+            PreviewMode::Extrude => preview::EXTRUDE,
+            PreviewMode::Revolve => preview::REVOLVE,
+        }
+    }
+}
+
+/// How the composition is shown, either as is or as a raymarched 3D solid
+pub struct Preview {
+    pub mode: PreviewMode,
+    pub height: f32,
+    pub rounding: f32,
+    /// Distance of the profile from the axis of revolution
+    pub offset: f32,
+    pub camera: Camera,
+    /// Where the extruded mesh is exported, as STL or OBJ
+    pub mesh_path: String,
+    /// Size of the cells of the exported mesh, in world units
+    pub cell_size: f32,
+}
+
+impl Default for Preview {
+    fn default() -> Self {
+        Self {
+            mode: PreviewMode::Flat,
+            height: 0.1,
+            rounding: 0.0,
+            offset: 0.0,
+            camera: Camera::default(),
+            mesh_path: "mesh.stl".to_string(),
+            cell_size: 0.002,
+        }
+    }
+}
+
+impl Preview {
+    pub fn is_3d(&self) -> bool {
+        self.mode != PreviewMode::Flat
+    }
+
+    /// Extrusion height or revolution offset, whichever the mode uses
+    pub fn size(&self) -> f32 {
+        match self.mode {
+            PreviewMode::Revolve => self.offset,
+            PreviewMode::Flat | PreviewMode::Extrude => self.height,
+        }
+    }
+
+    /// Turn the camera around the origin by a drag of `delta` in world units
+    pub fn orbit(&mut self, delta: Vec2) {
+        use std::f32::consts::FRAC_PI_2;
+
+        self.camera.yaw -= 4.0 * delta.x;
+        self.camera.pitch =
+            (self.camera.pitch + 4.0 * delta.y).clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+    }
+
+    /// Returns whether the export of a mesh was requested, which can't be while `exporting`
+    pub fn ui(&mut self, ui: &mut egui::Ui, exporting: bool) -> bool {
+        for mode in PreviewMode::iter() {
+            let label: &str = mode.into();
+            ui.radio_value(&mut self.mode, mode, label);
+        }
+        if !self.is_3d() {
+            return false;
+        }
+        ui.separator();
+        egui::Grid::new("preview_grid").show(ui, |ui| {
+            if self.mode == PreviewMode::Extrude {
+                ui.label("Height");
+                ui.add(
+                    egui::DragValue::new(&mut self.height)
+                        .range(0.0..=f64::INFINITY)
+                        .speed(0.001),
+                );
+                ui.end_row();
+                ui.label("Rounding");
+                ui.add(
+                    egui::DragValue::new(&mut self.rounding)
+                        .range(0.0..=f64::INFINITY)
+                        .speed(0.001),
+                );
+                ui.end_row();
+            } else {
+                ui.label("Offset");
+                ui.add(egui::DragValue::new(&mut self.offset).speed(0.001));
+                ui.end_row();
+            }
+            ui.label("Distance");
+            ui.add(
+                egui::DragValue::new(&mut self.camera.distance)
+                    .range(0.1..=f64::INFINITY)
+                    .speed(0.01),
+            );
+        });
+        if ui.button("Reset camera").clicked() {
+            self.camera = Camera::default();
+        }
+        if self.mode != PreviewMode::Extrude {
+            return false;
+        }
+        ui.separator();
+        ui.text_edit_singleline(&mut self.mesh_path);
+        ui.horizontal(|ui| {
+            ui.label("Cell size");
+            ui.add(
+                egui::DragValue::new(&mut self.cell_size)
+                    .range(0.0005..=0.1)
+                    .speed(0.0001),
+            );
+        });
+        let label = if exporting {
+            "Exporting..."
+        } else {
+            "Export mesh"
+        };
+        ui.add_enabled(!exporting, egui::Button::new(label))
+            .clicked()
+    }
+}
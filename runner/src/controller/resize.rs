@@ -72,6 +72,21 @@ impl Resize for LineSegment {
     }
 }
 
+impl Resize for CapsuleChain {
+    fn resize(mut self, initial: Vec2, current: Vec2, derivative: Vec2) -> Self {
+        let len = self.len as usize;
+        if let Some(i) = (0..len).find(|i| initial.distance(self.points[*i]) < 0.01) {
+            self.points[i] += current - initial;
+        } else {
+            let s = (current - initial) * derivative;
+            for radius in &mut self.radii[..len] {
+                *radius = (*radius + s.x + s.y).max(0.0);
+            }
+        }
+        self
+    }
+}
+
 impl Resize for Plane {
     fn resize(self, _initial: Vec2, _current: Vec2, _derivative: Vec2) -> Self {
         self
@@ -93,6 +108,7 @@ impl Resize for Shape {
             Shape::Rectangle(shape) => shape.resize(initial, current, derivative).into(),
             Shape::Cross(shape) => shape.resize(initial, current, derivative).into(),
             Shape::LineSegment(shape) => shape.resize(initial, current, derivative).into(),
+            Shape::CapsuleChain(shape) => shape.resize(initial, current, derivative).into(),
             Shape::Plane(shape) => shape.resize(initial, current, derivative).into(),
             Shape::Ray(shape) => shape.resize(initial, current, derivative).into(),
         }
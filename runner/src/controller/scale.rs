@@ -0,0 +1,89 @@
+use dfutils::primitives::*;
+use dfutils::primitives_enum::Shape;
+use glam::*;
+
+pub trait Scale {
+    /// Scale the size parameters of the shape, `factor` is per axis
+    fn scale(self, factor: Vec2) -> Self;
+}
+
+/// Factor for shapes without independent axes: the one that differs from 1 when constrained
+fn uniform(factor: Vec2) -> f32 {
+    if factor.x == 1.0 {
+        factor.y
+    } else {
+        factor.x
+    }
+}
+
+impl Scale for Disk {
+    fn scale(self, factor: Vec2) -> Self {
+        Disk::new(self.radius * uniform(factor))
+    }
+}
+
+impl Scale for Torus {
+    fn scale(self, factor: Vec2) -> Self {
+        let f = uniform(factor);
+        Torus::new(self.major_radius * f, self.minor_radius * f)
+    }
+}
+
+impl Scale for Rectangle {
+    fn scale(self, factor: Vec2) -> Self {
+        Rectangle::new(self.width * factor.x, self.height * factor.y)
+    }
+}
+
+impl Scale for Cross {
+    fn scale(self, factor: Vec2) -> Self {
+        let f = uniform(factor);
+        Cross::new(self.length * f, self.thickness * f)
+    }
+}
+
+impl Scale for LineSegment {
+    fn scale(self, factor: Vec2) -> Self {
+        LineSegment::new(self.a * factor, self.b * factor)
+    }
+}
+
+impl Scale for CapsuleChain {
+    fn scale(mut self, factor: Vec2) -> Self {
+        let len = self.len as usize;
+        let f = uniform(factor);
+        for (point, radius) in self.points[..len].iter_mut().zip(&mut self.radii[..len]) {
+            *point *= factor;
+            *radius *= f;
+        }
+        self
+    }
+}
+
+impl Scale for Plane {
+    fn scale(self, _factor: Vec2) -> Self {
+        self
+    }
+}
+
+impl Scale for Ray {
+    fn scale(self, _factor: Vec2) -> Self {
+        self
+    }
+}
+
+impl Scale for Shape {
+    fn scale(self, factor: Vec2) -> Self {
+        let factor = factor.abs();
+        match self {
+            Shape::Disk(shape) => shape.scale(factor).into(),
+            Shape::Torus(shape) => shape.scale(factor).into(),
+            Shape::Rectangle(shape) => shape.scale(factor).into(),
+            Shape::Cross(shape) => shape.scale(factor).into(),
+            Shape::LineSegment(shape) => shape.scale(factor).into(),
+            Shape::CapsuleChain(shape) => shape.scale(factor).into(),
+            Shape::Plane(shape) => shape.scale(factor).into(),
+            Shape::Ray(shape) => shape.scale(factor).into(),
+        }
+    }
+}
@@ -1,57 +1,24 @@
-use super::{icons::TextureHandles, shape_ui::ShapeUi};
-use dfutils::primitives_enum::Shape;
-use egui::{load::SizedTexture, NumExt as _, TextureHandle};
+use super::{icons::TextureHandles, modifier_ui::ModifierUi, shape_ui::ShapeUi};
+use dfutils::{grid::Grid, mesh::Mesh, primitives_enum::Shape, sdf3::Extrude};
+use egui::{load::SizedTexture, TextureHandle};
 use egui_winit::winit::dpi::PhysicalSize;
 use glam::*;
 use itertools::izip;
+use sdf_scene::{
+    animation::{Interpolation, Keyframe},
+    Component, ComponentId, Item, ItemId, ItemProperties, Scene, Variable,
+};
 use shared::{
     from_pixels,
-    sdf_interpreter::{Instruction, Operator, Transform},
-    sdf_wrapper::SdfWrapper,
+    sdf_interpreter::{Instruction, Modifier, Operator, SdfInstructions, Transform},
+    sdf_wrapper::{SdfWrapper, WrappedDistance},
+};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
 };
-use std::collections::HashMap;
 use strum::IntoEnumIterator;
 
-#[repr(C)]
-#[derive(bytemuck::Pod, bytemuck::Zeroable, Hash, Clone, Copy, PartialEq, Eq, Default)]
-pub struct ItemId(pub u32);
-
-impl ItemId {
-    fn new() -> Self {
-        Self(rand::random())
-    }
-}
-
-impl std::fmt::Debug for ItemId {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "#{:04x}", self.0)
-    }
-}
-
-impl From<ItemId> for egui::Id {
-    fn from(id: ItemId) -> Self {
-        Self::new(id)
-    }
-}
-
-#[derive(Clone, Debug)]
-pub enum Item {
-    Operator(Operator, Vec<ItemId>),
-    Shape(Shape, Transform),
-}
-
-impl From<Shape> for Item {
-    fn from(shape: Shape) -> Self {
-        Item::Shape(shape, Default::default())
-    }
-}
-
-impl From<Operator> for Item {
-    fn from(op: Operator) -> Self {
-        Item::Operator(op, Default::default())
-    }
-}
-
 #[derive(Debug)]
 pub struct SelectedItem {
     pub id: Option<ItemId>,
@@ -104,19 +71,46 @@ pub enum Command {
     /// Edit the selected item.
     EditItem { item: Item, item_id: ItemId },
 
+    /// Combine a new item with the top level items using the current operator, and select it.
+    CombineItem { item: Item, item_id: ItemId },
+
     /// Remove the selected item.
     RemoveItem { item_id: ItemId },
 
+    /// Change the name, visibility or lock state of an item.
+    SetItemProperties {
+        item_id: ItemId,
+        properties: ItemProperties,
+    },
+
+    /// Turn an item into the definition of a new component, and put an instance in its place.
+    MakeComponent { item_id: ItemId },
+
+    /// Replace an instance with a copy of its component that can be edited on its own.
+    DetachInstance { item_id: ItemId },
+
+    /// Rename a component.
+    RenameComponent {
+        component_id: ComponentId,
+        name: String,
+    },
+
+    /// Replace the scene variables.
+    SetVariables(Vec<Variable>),
+
+    /// Remove a component along with all its instances.
+    RemoveComponent(ComponentId),
+
     /// Specify the currently identified target container to be highlighted.
     HighlightTargetContainer(ItemId),
 }
 
 pub struct SdfBuilderTree {
-    /// All items
-    items: HashMap<ItemId, Item>,
+    /// The composition being edited
+    pub scene: Scene,
 
-    /// Id of the root item (not displayed in the UI)
-    root_id: ItemId,
+    /// Interpolation of the keys added from the timeline
+    key_interpolation: Interpolation,
 
     /// Selected item, if any
     pub selected_item: SelectedItem,
@@ -141,14 +135,11 @@ pub struct SdfBuilderTree {
 
 impl Default for SdfBuilderTree {
     fn default() -> Self {
-        let root_item = Item::Operator(Operator::Union, Vec::new());
-        let root_id = ItemId::new();
-
         let (command_sender, command_receiver) = std::sync::mpsc::channel();
 
-        let mut res = Self {
-            items: std::iter::once((root_id, root_item)).collect(),
-            root_id,
+        Self {
+            scene: Scene::new(),
+            key_interpolation: Interpolation::Linear,
             selected_item: SelectedItem::NONE,
             target_container: None,
             command_receiver,
@@ -156,11 +147,7 @@ impl Default for SdfBuilderTree {
             grid_needs_updating: true,
             extra_item: None,
             operator_mode: Operator::Union,
-        };
-
-        res.populate();
-
-        res
+        }
     }
 }
 
@@ -168,168 +155,151 @@ impl Default for SdfBuilderTree {
 // Data stuff
 //
 impl SdfBuilderTree {
-    fn populate(&mut self) {
-        // self.add_leaf(self.root_id, Shape::Rectangle(Default::default()));
-    }
-
-    fn get_root_children(&self) -> &Vec<ItemId> {
-        let Some(Item::Operator(_, children)) = self.items.get(&self.root_id) else {
-            panic!("no root")
-        };
-        children
-    }
-
-    fn get_root_children_mut(&mut self) -> &mut Vec<ItemId> {
-        let Some(Item::Operator(_, children)) = self.items.get_mut(&self.root_id) else {
-            panic!("no root")
-        };
-        children
-    }
-
     pub fn get_selected_item(&self) -> Option<&Item> {
-        self.selected_item.id.and_then(|id| self.items.get(&id))
-    }
-
-    fn container(&self, id: ItemId) -> Option<&Vec<ItemId>> {
-        match self.items.get(&id) {
-            Some(Item::Operator(_, children)) => Some(children),
-            _ => None,
-        }
+        self.selected_item.id.and_then(|id| self.scene.item(id))
     }
 
-    /// Does some container contain the given item?
-    ///
-    /// Used to test if a target location is suitable for a given dragged item.
-    fn contains(&self, container_id: ItemId, item_id: ItemId) -> bool {
-        if let Some(children) = self.container(container_id) {
-            if container_id == item_id {
-                return true;
-            }
-
-            if children.contains(&item_id) {
-                return true;
-            }
-
-            for child_id in children {
-                if self.contains(*child_id, item_id) {
-                    return true;
-                }
+    /// Human readable label of an item, including the one being dragged in.
+    pub fn label(&self, id: ItemId) -> String {
+        match &self.selected_item.new_item {
+            Some(Item::Shape(shape, _))
+                if self.selected_item.id == Some(id) && self.scene.item(id).is_none() =>
+            {
+                format!("New {}", Into::<&str>::into(shape))
             }
-
-            return false;
+            _ => self.scene.label(id),
         }
-
-        false
     }
 
-    /// Move item `item_id` to `container_id` at position `pos`.
-    fn move_item(&mut self, item_id: ItemId, container_id: ItemId, mut pos: usize) {
-        println!("Moving {item_id:?} to {container_id:?} at position {pos:?}");
-
-        // Remove the item from its current location. Note: we must adjust the target position if the item is
-        // moved within the same container, as the removal might shift the positions by one.
-        if let Some((source_parent_id, source_pos)) = self.parent_and_pos(item_id) {
-            if let Some(Item::Operator(_, children)) = self.items.get_mut(&source_parent_id) {
-                children.remove(source_pos);
-            }
-
-            if source_parent_id == container_id && source_pos < pos {
-                pos -= 1;
-            }
+    /// Labels of the operators from the top of the tree down to the given item, followed by the
+    /// item itself.
+    pub fn path_labels(&self, id: ItemId) -> Vec<String> {
+        let mut path = self.scene.path_labels(id);
+        if let Some(label) = path.last_mut() {
+            *label = self.label(id);
         }
+        path
+    }
 
-        if let Some(Item::Operator(_, children)) = self.items.get_mut(&container_id) {
-            children.insert(pos.at_most(children.len()), item_id);
+    /// Key every parameter of an item at the current time.
+    fn key_item(&self, id: ItemId) {
+        let mut properties = self.scene.properties(id);
+        for (name, value) in self.scene.item_parameters(id) {
+            properties
+                .tracks
+                .entry(name.to_string())
+                .or_default()
+                .set_key(Keyframe {
+                    time: self.scene.timeline.time,
+                    value,
+                    interpolation: self.key_interpolation,
+                });
         }
+        self.send_command(Command::SetItemProperties {
+            item_id: id,
+            properties,
+        });
     }
 
-    /// Add item `item_id` to `container_id` at position `pos`.
-    fn add_item(&mut self, item: Item, item_id: ItemId, container_id: ItemId, pos: usize) {
-        println!("Adding {item_id:?} to {container_id:?} at position {pos:?}");
-
-        self.items.insert(item_id, item);
-
-        if let Some(Item::Operator(_, children)) = self.items.get_mut(&container_id) {
-            children.insert(pos.at_most(children.len()), item_id);
+    /// Rasterize every frame of the timeline on the CPU and write them as PNG images, returns the
+    /// number of frames written.
+    pub fn export_frames(&mut self) -> std::io::Result<usize> {
+        let timeline = &mut self.scene.timeline;
+        let dir = PathBuf::from(&timeline.export_dir);
+        std::fs::create_dir_all(&dir)?;
+        let time = timeline.time;
+        let (w, h) = timeline.export_size;
+        let frame_count = timeline.frame_count();
+        for frame in 0..frame_count {
+            self.scene.timeline.time = frame as f32 / self.scene.timeline.fps as f32;
+            self.scene.apply_parameters();
+            let instructions = self.scene.generate_instructions();
+            let grid = Grid::from_sdf(w, h, &SdfInstructions::new(&instructions));
+            write_png(&dir.join(format!("frame_{frame:04}.png")), &grid)?;
         }
+        self.scene.timeline.time = time;
+        self.scene.apply_parameters();
+        Ok(frame_count)
     }
 
-    /// Edit item `item_id`.
-    fn edit_item(&mut self, item: Item, item_id: ItemId) {
-        println!("Editing {item_id:?}");
-
-        *self.items.get_mut(&item_id).unwrap() = item.clone();
+    /// Mesh the composition extruded along Z, within `half_size` of the origin in the XY plane,
+    /// and write it as STL or OBJ depending on the extension of `path`. Returns the number of
+    /// triangles.
+    pub fn export_mesh(
+        &mut self,
+        path: &Path,
+        height: f32,
+        rounding: f32,
+        half_size: Vec2,
+        cell_size: f32,
+    ) -> std::io::Result<usize> {
+        self.scene.apply_parameters();
+        let instructions = self.scene.generate_instructions();
+        write_mesh(&instructions, path, height, rounding, half_size, cell_size)
     }
 
-    /// Remove item `item_id`.
-    fn remove_item(&mut self, item_id: ItemId) {
-        println!("Removing {item_id:?}");
-
-        let item = self.items.get(&item_id).unwrap();
-        match item {
-            Item::Operator(_, items) => {
-                for id in items {
-                    self.send_command(Command::RemoveItem { item_id: *id })
-                }
-            }
-            Item::Shape(_, _) => {}
-        }
-        if let Some((id, pos)) = self.parent_and_pos(item_id) {
-            match self.items.get_mut(&id).unwrap() {
-                Item::Operator(_, items) => {
-                    items.remove(pos);
-                }
-                Item::Shape(_, _) => {}
-            }
-        }
-        self.items.remove(&item_id);
+    pub fn send_command(&self, command: Command) {
+        // The only way this can fail is if the receiver has been dropped.
+        self.command_sender.send(command).ok();
     }
 
-    /// Find the parent of an item, and the index of that item within the parent's children.
-    fn parent_and_pos(&self, id: ItemId) -> Option<(ItemId, usize)> {
-        if id == self.root_id {
-            None
-        } else {
-            self.parent_and_pos_impl(id, self.root_id)
-        }
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        self.scene.save(path)
     }
 
-    fn parent_and_pos_impl(&self, id: ItemId, container_id: ItemId) -> Option<(ItemId, usize)> {
-        if let Some(children) = self.container(container_id) {
-            for (idx, child_id) in children.iter().enumerate() {
-                if child_id == &id {
-                    return Some((container_id, idx));
-                } else if self.container(*child_id).is_some() {
-                    let res = self.parent_and_pos_impl(id, *child_id);
-                    if res.is_some() {
-                        return res;
-                    }
-                }
-            }
-        }
-
-        None
+    pub fn load(&mut self, path: &Path) -> std::io::Result<()> {
+        self.scene = Scene::load(path)?;
+        self.selected_item = SelectedItem::NONE;
+        self.grid_needs_updating = true;
+        Ok(())
     }
+}
 
-    #[allow(dead_code)]
-    fn add_leaf(&mut self, parent_id: ItemId, shape: Shape) {
-        let id = ItemId::new();
-
-        self.items.insert(id, shape.into());
-
-        if let Some(Item::Operator(_, children)) = self.items.get_mut(&parent_id) {
-            children.push(id);
-        }
+/// Mesh `instructions` extruded along Z, like [SdfBuilderTree::export_mesh], without needing the
+/// tree so that it can run on another thread.
+pub fn write_mesh(
+    instructions: &[Instruction<SdfWrapper<Shape, ItemId>>],
+    path: &Path,
+    height: f32,
+    rounding: f32,
+    half_size: Vec2,
+    cell_size: f32,
+) -> std::io::Result<usize> {
+    let extension = path.extension().and_then(|e| e.to_str());
+    if !matches!(extension, Some("stl" | "obj")) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "mesh files must end in .stl or .obj",
+        ));
     }
-
-    pub fn send_command(&self, command: Command) {
-        // The only way this can fail is if the receiver has been dropped.
-        self.command_sender.send(command).ok();
+    let extruded = Extrude::new(SdfInstructions::new(instructions), height, rounding);
+    let half_size = half_size.extend(0.5 * height);
+    let mesh = Mesh::from_sdf(&extruded, -half_size, half_size, cell_size);
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    if extension == Some("stl") {
+        mesh.write_stl(file)?;
+    } else {
+        mesh.write_obj(file)?;
     }
+    Ok(mesh.triangles.len())
+}
 
-    pub fn size(&self) -> usize {
-        self.items.len() - 1
-    }
+/// Write the coverage of a rasterized frame as a grayscale image, shapes in white.
+fn write_png(path: &Path, grid: &Grid<WrappedDistance<ItemId>>) -> std::io::Result<()> {
+    let pixels: Vec<u8> = grid.buffer[..grid.w * grid.h]
+        .iter()
+        .map(|value| {
+            let d = value.d;
+            (255.0 * (0.5 - d * grid.h as f32).clamp(0.0, 1.0)) as u8
+        })
+        .collect();
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut encoder = png::Encoder::new(file, grid.w as u32, grid.h as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&pixels)?;
+    Ok(())
 }
 
 //
@@ -357,7 +327,7 @@ impl SdfBuilderTree {
             .inner_margin(egui::Margin::same(3.0))
             .show(ui, |ui| {
                 egui::ScrollArea::both().show(ui, |ui| {
-                    let top_level_items = self.get_root_children();
+                    let top_level_items = self.scene.root_children();
                     if top_level_items.is_empty() {
                         self.root_drop_target(ui);
                     } else {
@@ -366,6 +336,22 @@ impl SdfBuilderTree {
                 });
             });
 
+        ui.separator();
+        ui.vertical_centered(|ui| {
+            ui.label(egui::RichText::new("Variables").size(16.0));
+        });
+        self.variables_ui(ui);
+
+        if self.scene.components().next().is_some() {
+            ui.separator();
+            ui.vertical_centered(|ui| {
+                ui.label(egui::RichText::new("Components").size(16.0));
+            });
+            for (component_id, component) in self.scene.components() {
+                self.component_ui(ui, component_id, component);
+            }
+        }
+
         self.handle_extra_item(ui, size);
 
         // deselect by clicking in the empty space
@@ -392,7 +378,8 @@ impl SdfBuilderTree {
                     target_container_id,
                     target_position_index,
                 } => {
-                    self.move_item(moved_item_id, target_container_id, target_position_index);
+                    self.scene
+                        .move_item(moved_item_id, target_container_id, target_position_index);
                     self.grid_needs_updating = true;
                 }
                 Command::AddItem {
@@ -401,7 +388,7 @@ impl SdfBuilderTree {
                     target_container_id,
                     target_position_index,
                 } => {
-                    self.add_item(
+                    self.scene.add_item(
                         item,
                         new_item_id,
                         target_container_id,
@@ -410,11 +397,53 @@ impl SdfBuilderTree {
                     self.grid_needs_updating = true;
                 }
                 Command::EditItem { item, item_id } => {
-                    self.edit_item(item, item_id);
+                    self.scene.edit_item(item, item_id);
+                    self.grid_needs_updating = true;
+                }
+                Command::CombineItem { item, item_id } => {
+                    self.scene.combine_item(self.operator_mode, item, item_id);
+                    self.selected_item = item_id.into();
                     self.grid_needs_updating = true;
                 }
                 Command::RemoveItem { item_id } => {
-                    self.remove_item(item_id);
+                    self.scene.remove_item(item_id);
+                    self.grid_needs_updating = true;
+                }
+                Command::SetItemProperties {
+                    item_id,
+                    properties,
+                } => {
+                    let previous = self.scene.properties(item_id);
+                    if properties.hidden != previous.hidden
+                        || properties.bindings != previous.bindings
+                        || properties.tracks != previous.tracks
+                    {
+                        self.grid_needs_updating = true;
+                    }
+                    self.scene.set_properties(item_id, properties);
+                }
+                Command::MakeComponent { item_id } => {
+                    if let Some(instance_id) = self.scene.make_component(item_id) {
+                        self.selected_item = instance_id.into();
+                    }
+                }
+                Command::DetachInstance { item_id } => {
+                    if let Some(copy_id) = self.scene.detach_instance(item_id) {
+                        if self.selected_item.id == Some(item_id) {
+                            self.selected_item = copy_id.into();
+                        }
+                    }
+                    self.grid_needs_updating = true;
+                }
+                Command::RenameComponent { component_id, name } => {
+                    self.scene.rename_component(component_id, name);
+                }
+                Command::SetVariables(variables) => {
+                    self.scene.set_variables(variables);
+                    self.grid_needs_updating = true;
+                }
+                Command::RemoveComponent(component_id) => {
+                    self.scene.remove_component(component_id);
                     self.grid_needs_updating = true;
                 }
                 Command::HighlightTargetContainer(item_id) => {
@@ -422,6 +451,11 @@ impl SdfBuilderTree {
                 }
             }
         }
+
+        // bound parameters follow the variables and the items they are bound to
+        if self.grid_needs_updating {
+            self.apply_parameters();
+        }
     }
 
     fn shapes_ui(&self, ui: &mut egui::Ui, icons: &[TextureHandle]) {
@@ -454,7 +488,7 @@ impl SdfBuilderTree {
                 }
             }
         };
-        let size = self.size();
+        let size = self.scene.item_count();
         let h = if size == 0 {
             44.0
         } else {
@@ -503,38 +537,49 @@ impl SdfBuilderTree {
                 }
             }
         });
+        ui.horizontal(|ui| {
+            let nodes = std::iter::once((
+                "Lerp",
+                "Interpolate between two items",
+                Item::Lerp(0.5, Vec::new()),
+            ))
+            .chain(Modifier::iter().map(|modifier| {
+                (
+                    modifier.into(),
+                    "Modify the distance of an item",
+                    Item::Modifier(modifier, Vec::new()),
+                )
+            }));
+            for (label, hover_text, item) in nodes {
+                let response = ui
+                    .add(
+                        egui::Label::new(label)
+                            .selectable(false)
+                            .sense(egui::Sense::click_and_drag()),
+                    )
+                    .on_hover_text(format!("{hover_text}, drag into the tree"));
+                self.handle_new_item_drag(ui, &response, item);
+            }
+        });
     }
 
-    fn container_ui(
-        &self,
-        ui: &mut egui::Ui,
-        item_id: ItemId,
-        operator: &Operator,
-        children: &Vec<ItemId>,
-    ) {
+    /// An operator, lerp or modifier node, with its parameters above its children
+    fn container_ui(&self, ui: &mut egui::Ui, item_id: ItemId, item: &Item) {
+        let Some(children) = item.children() else {
+            return;
+        };
         let (response, head_response, body_resp) =
             egui::collapsing_header::CollapsingState::load_with_default_open(
                 ui.ctx(),
-                item_id.into(),
+                egui::Id::new(item_id),
                 true,
             )
             .show_header(ui, |ui| {
-                let resp = ui.add(
-                    egui::Label::new(format!("{operator:?}"))
-                        .selectable(false)
-                        .sense(egui::Sense::click_and_drag()),
-                );
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
-                    let resp = ui.button("x");
-                    if resp.clicked() {
-                        self.send_command(Command::RemoveItem { item_id });
-                    }
-                    resp
-                })
-                .inner
-                .union(resp)
+                let resp = self.header_label(ui, item_id);
+                self.header_buttons(ui, item_id).union(resp)
             })
             .body(|ui| {
+                self.node_params_ui(ui, item_id, item);
                 self.container_children_ui(ui, children);
             });
 
@@ -575,41 +620,62 @@ impl SdfBuilderTree {
                 None
             };
 
-            match self.items.get(child_id) {
-                Some(Item::Operator(operator, children)) => {
-                    self.container_ui(ui, *child_id, operator, children);
+            match self.scene.item(*child_id) {
+                Some(item @ (Item::Operator(_, _) | Item::Lerp(_, _) | Item::Modifier(_, _))) => {
+                    self.container_ui(ui, *child_id, item);
                 }
                 Some(Item::Shape(shape, transform)) => {
                     self.leaf_ui(ui, *child_id, *shape, *transform);
                 }
+                Some(Item::Instance(component_id, transform)) => {
+                    self.instance_ui(ui, *child_id, *component_id, *transform);
+                }
                 None => {}
             }
         }
     }
 
+    fn node_params_ui(&self, ui: &mut egui::Ui, item_id: ItemId, item: &Item) {
+        let new_item = match item {
+            Item::Lerp(t, children) => {
+                let mut new_t = *t;
+                ui.add(egui::Slider::new(&mut new_t, 0.0..=1.0).text("t"));
+                Item::Lerp(new_t, children.clone())
+            }
+            Item::Modifier(modifier, children) => {
+                let new_modifier = egui::Grid::new("modifier_params_grid")
+                    .show(ui, |ui| modifier.ui(ui))
+                    .inner;
+                Item::Modifier(new_modifier, children.clone())
+            }
+            _ => return,
+        };
+        let changed = match (item, &new_item) {
+            (Item::Lerp(a, _), Item::Lerp(b, _)) => a != b,
+            (Item::Modifier(a, _), Item::Modifier(b, _)) => a != b,
+            _ => false,
+        };
+        if changed {
+            self.send_command(Command::EditItem {
+                item: new_item,
+                item_id,
+            });
+        }
+        if !self.scene.item_parameters(item_id).is_empty() {
+            self.bindings_ui(ui, item_id, &self.scene.item_parameters(item_id));
+        }
+    }
+
     fn leaf_ui(&self, ui: &mut egui::Ui, item_id: ItemId, shape: Shape, transform: Transform) {
         let (response, head_response, body_resp) =
             egui::collapsing_header::CollapsingState::load_with_default_open(
                 ui.ctx(),
-                item_id.into(),
+                egui::Id::new(item_id),
                 false,
             )
             .show_header(ui, |ui| {
-                let label: &str = shape.into();
-                let resp = ui.add(
-                    egui::Label::new(label)
-                        .selectable(false)
-                        .sense(egui::Sense::click_and_drag()),
-                );
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
-                    let resp = ui.button("x");
-                    if resp.clicked() {
-                        self.send_command(Command::RemoveItem { item_id });
-                    }
-                    resp
-                })
-                .inner
-                .union(resp)
+                let resp = self.header_label(ui, item_id);
+                self.header_buttons(ui, item_id).union(resp)
             })
             .body(|ui| {
                 egui::Grid::new("shape_params_grid").show(ui, |ui| {
@@ -619,6 +685,9 @@ impl SdfBuilderTree {
                     ui.label("pos");
                     ui.add(egui::DragValue::new(&mut new_transform.position.x).speed(0.01));
                     ui.add(egui::DragValue::new(&mut new_transform.position.y).speed(0.01));
+                    ui.end_row();
+                    ui.label("rot");
+                    ui.drag_angle(&mut new_transform.rotation);
                     if shape != new_shape || transform != new_transform {
                         self.send_command(Command::EditItem {
                             item: Item::Shape(new_shape, new_transform),
@@ -626,6 +695,7 @@ impl SdfBuilderTree {
                         });
                     }
                 });
+                self.bindings_ui(ui, item_id, &self.scene.item_parameters(item_id));
             });
 
         if head_response.inner.clicked() {
@@ -640,9 +710,374 @@ impl SdfBuilderTree {
         self.handle_drag_and_drop_interaction(ui, item_id, false, &response, None);
     }
 
+    /// Playback controls, scrubbing, keying of the selected item and export of frames
+    pub fn timeline_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let play_label = if self.scene.timeline.playing {
+                "⏸"
+            } else {
+                "▶"
+            };
+            if ui.button(play_label).clicked() {
+                if !self.scene.timeline.playing
+                    && self.scene.timeline.time >= self.scene.timeline.duration
+                {
+                    self.scene.timeline.time = 0.0;
+                }
+                self.scene.timeline.playing = !self.scene.timeline.playing;
+            }
+            ui.checkbox(&mut self.scene.timeline.looping, "loop");
+
+            let selected_id = self
+                .selected_item
+                .id
+                .filter(|id| self.scene.item(*id).is_some());
+            let key_times = selected_id.map_or(vec![], |id| self.scene.key_times(id));
+            let time = self.scene.timeline.time;
+            let previous_key = key_times.iter().rev().find(|t| **t < time - 1e-3);
+            let next_key = key_times.iter().find(|t| **t > time + 1e-3);
+            let mut new_time = None;
+            if ui
+                .add_enabled(previous_key.is_some(), egui::Button::new("◀◆"))
+                .on_hover_text("Previous key")
+                .clicked()
+            {
+                new_time = previous_key.copied();
+            }
+            ui.spacing_mut().slider_width = (ui.available_width() - 480.0).max(100.0);
+            let mut scrubbed = time;
+            if ui
+                .add(
+                    egui::Slider::new(&mut scrubbed, 0.0..=self.scene.timeline.duration)
+                        .suffix(" s"),
+                )
+                .changed()
+            {
+                new_time = Some(scrubbed);
+            }
+            if ui
+                .add_enabled(next_key.is_some(), egui::Button::new("◆▶"))
+                .on_hover_text("Next key")
+                .clicked()
+            {
+                new_time = next_key.copied();
+            }
+            if let Some(time) = new_time {
+                self.scene.timeline.time = time;
+                self.grid_needs_updating = true;
+            }
+            ui.label("duration");
+            ui.add(
+                egui::DragValue::new(&mut self.scene.timeline.duration)
+                    .range(0.1..=f64::INFINITY)
+                    .speed(0.1)
+                    .suffix(" s"),
+            );
+            ui.separator();
+
+            egui::ComboBox::from_id_source("key_interpolation")
+                .selected_text(Into::<&str>::into(self.key_interpolation))
+                .show_ui(ui, |ui| {
+                    for interpolation in Interpolation::iter() {
+                        let label: &str = interpolation.into();
+                        ui.selectable_value(&mut self.key_interpolation, interpolation, label);
+                    }
+                });
+            if let Some(id) = selected_id {
+                if ui
+                    .button("Key")
+                    .on_hover_text("Key the selected item")
+                    .clicked()
+                {
+                    self.key_item(id);
+                }
+                let properties = self.scene.properties(id);
+                if ui
+                    .add_enabled(
+                        !properties.tracks.is_empty(),
+                        egui::Button::new("Clear keys"),
+                    )
+                    .clicked()
+                {
+                    self.send_command(Command::SetItemProperties {
+                        item_id: id,
+                        properties: ItemProperties {
+                            tracks: BTreeMap::new(),
+                            ..properties
+                        },
+                    });
+                }
+            }
+            ui.separator();
+
+            ui.add(
+                egui::DragValue::new(&mut self.scene.timeline.fps)
+                    .range(1..=240)
+                    .suffix(" fps"),
+            );
+            if ui
+                .button("Export")
+                .on_hover_text(format!(
+                    "Write the frames as PNG images to {}/",
+                    self.scene.timeline.export_dir
+                ))
+                .clicked()
+            {
+                match self.export_frames() {
+                    Ok(count) => {
+                        println!(
+                            "Exported {count} frames to {}",
+                            self.scene.timeline.export_dir
+                        )
+                    }
+                    Err(e) => eprintln!("Failed to export frames: {e}"),
+                }
+            }
+        });
+    }
+
+    fn variables_ui(&self, ui: &mut egui::Ui) {
+        let mut variables = self.scene.variables().to_vec();
+        let mut changed = false;
+        let mut removed = None;
+        egui::Grid::new("variables_grid").show(ui, |ui| {
+            for (index, variable) in variables.iter_mut().enumerate() {
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut variable.name).desired_width(50.0))
+                    .changed();
+                ui.label("=");
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut variable.expression).desired_width(90.0))
+                    .changed();
+                match self.scene.variable_values().get(&variable.name) {
+                    Some(value) => ui.weak(format!("{value:.3}")),
+                    None => ui.weak("?"),
+                };
+                if ui.button("x").clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = removed {
+            variables.remove(index);
+            changed = true;
+        }
+        if ui.button("Add variable").clicked() {
+            variables.push(Variable {
+                name: format!("v{}", variables.len() + 1),
+                expression: "0".to_string(),
+            });
+            changed = true;
+        }
+        for error in self.scene.parameter_errors() {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        if changed {
+            self.send_command(Command::SetVariables(variables));
+        }
+    }
+
+    /// Expressions bound to the parameters of an item, an empty expression leaves the parameter
+    /// free
+    fn bindings_ui(&self, ui: &mut egui::Ui, item_id: ItemId, parameters: &[(&str, f32)]) {
+        ui.collapsing("Bindings", |ui| {
+            let mut properties = self.scene.properties(item_id);
+            egui::Grid::new("bindings_grid").show(ui, |ui| {
+                for (name, _) in parameters {
+                    ui.label(*name);
+                    let mut source = properties.bindings.get(*name).cloned().unwrap_or_default();
+                    if ui
+                        .add(egui::TextEdit::singleline(&mut source).desired_width(90.0))
+                        .changed()
+                    {
+                        if source.trim().is_empty() {
+                            properties.bindings.remove(*name);
+                        } else {
+                            properties.bindings.insert(name.to_string(), source);
+                        }
+                        self.send_command(Command::SetItemProperties {
+                            item_id,
+                            properties: properties.clone(),
+                        });
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn instance_ui(
+        &self,
+        ui: &mut egui::Ui,
+        item_id: ItemId,
+        component_id: ComponentId,
+        transform: Transform,
+    ) {
+        let (response, head_response, body_resp) =
+            egui::collapsing_header::CollapsingState::load_with_default_open(
+                ui.ctx(),
+                egui::Id::new(item_id),
+                false,
+            )
+            .show_header(ui, |ui| {
+                let resp = self.header_label(ui, item_id);
+                self.header_buttons(ui, item_id).union(resp)
+            })
+            .body(|ui| {
+                egui::Grid::new("instance_params_grid").show(ui, |ui| {
+                    let mut new_transform = transform;
+                    ui.label("pos");
+                    ui.add(egui::DragValue::new(&mut new_transform.position.x).speed(0.01));
+                    ui.add(egui::DragValue::new(&mut new_transform.position.y).speed(0.01));
+                    ui.end_row();
+                    ui.label("rot");
+                    ui.drag_angle(&mut new_transform.rotation);
+                    ui.end_row();
+                    if transform != new_transform {
+                        self.send_command(Command::EditItem {
+                            item: Item::Instance(component_id, new_transform),
+                            item_id,
+                        });
+                    }
+                });
+                self.bindings_ui(ui, item_id, &transform.parameters());
+                if ui.button("Detach").clicked() {
+                    self.send_command(Command::DetachInstance { item_id });
+                }
+            });
+
+        if head_response.inner.clicked() {
+            self.send_command(Command::SetSelectedItem(item_id.into()));
+        }
+
+        let mut response = head_response.inner.union(response);
+        if let Some(resp) = body_resp {
+            response = response.union(resp.response);
+        }
+
+        self.handle_drag_and_drop_interaction(ui, item_id, false, &response, None);
+    }
+
+    /// A component definition: its name can be dragged into the tree to add an instance, and its
+    /// items are edited like those of the composition tree.
+    fn component_ui(&self, ui: &mut egui::Ui, component_id: ComponentId, component: &Component) {
+        let Some(Item::Operator(_, children)) = self.scene.item(component.root_id) else {
+            return;
+        };
+        egui::collapsing_header::CollapsingState::load_with_default_open(
+            ui.ctx(),
+            egui::Id::new(component_id),
+            false,
+        )
+        .show_header(ui, |ui| {
+            let resp = ui.add(
+                egui::Label::new(&component.name)
+                    .selectable(false)
+                    .sense(egui::Sense::click_and_drag()),
+            );
+            resp.context_menu(|ui| {
+                ui.label("Name");
+                let mut name = component.name.clone();
+                if ui.text_edit_singleline(&mut name).changed() {
+                    self.send_command(Command::RenameComponent { component_id, name });
+                }
+            });
+            self.handle_new_item_drag(
+                ui,
+                &resp,
+                Item::Instance(component_id, Transform::default()),
+            );
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                if ui
+                    .button("x")
+                    .on_hover_text("Remove the component and its instances")
+                    .clicked()
+                {
+                    self.send_command(Command::RemoveComponent(component_id));
+                }
+            });
+        })
+        .body(|ui| {
+            self.container_children_ui(ui, children);
+        });
+    }
+
+    /// Name of an item, which can be changed from its context menu
+    fn header_label(&self, ui: &mut egui::Ui, item_id: ItemId) -> egui::Response {
+        let mut properties = self.scene.properties(item_id);
+        let mut text = egui::RichText::new(self.label(item_id));
+        if properties.hidden {
+            text = text.weak();
+        }
+        let resp = ui.add(
+            egui::Label::new(text)
+                .selectable(false)
+                .sense(egui::Sense::click_and_drag()),
+        );
+        resp.context_menu(|ui| {
+            ui.label("Name");
+            if ui.text_edit_singleline(&mut properties.name).changed() {
+                self.send_command(Command::SetItemProperties {
+                    item_id,
+                    properties: properties.clone(),
+                });
+            }
+            ui.separator();
+            if let Some(Item::Instance(_, _)) = self.scene.item(item_id) {
+                if ui.button("Detach instance").clicked() {
+                    self.send_command(Command::DetachInstance { item_id });
+                    ui.close_menu();
+                }
+            }
+            if ui.button("Make component").clicked() {
+                self.send_command(Command::MakeComponent { item_id });
+                ui.close_menu();
+            }
+        });
+        resp
+    }
+
+    /// Remove, lock and visibility buttons on the right of an item's header
+    fn header_buttons(&self, ui: &mut egui::Ui, item_id: ItemId) -> egui::Response {
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+            let properties = self.scene.properties(item_id);
+            let remove = ui.button("x");
+            if remove.clicked() {
+                self.send_command(Command::RemoveItem { item_id });
+            }
+            let lock = ui
+                .selectable_label(properties.locked, "🔒")
+                .on_hover_text("Lock in the canvas");
+            if lock.clicked() {
+                self.send_command(Command::SetItemProperties {
+                    item_id,
+                    properties: ItemProperties {
+                        locked: !properties.locked,
+                        ..properties.clone()
+                    },
+                });
+            }
+            let visibility = ui
+                .selectable_label(!properties.hidden, "👁")
+                .on_hover_text("Visible");
+            if visibility.clicked() {
+                self.send_command(Command::SetItemProperties {
+                    item_id,
+                    properties: ItemProperties {
+                        hidden: !properties.hidden,
+                        ..properties
+                    },
+                });
+            }
+            remove.union(lock).union(visibility)
+        })
+        .inner
+    }
+
     fn handle_new_item_drag(&self, ui: &egui::Ui, response: &egui::Response, new_item: Item) {
         if response.drag_started() {
-            let item_id = ItemId::new();
+            let item_id = ItemId::random();
             egui::DragAndDrop::set_payload(ui.ctx(), item_id);
 
             self.send_command(Command::SetSelectedItem(SelectedItem::new(
@@ -689,9 +1124,10 @@ impl SdfBuilderTree {
         };
 
         let previous_container_id = if position_index_in_parent > 0 {
-            self.container(parent_id)
+            self.scene
+                .container(parent_id)
                 .map(|c| c[position_index_in_parent - 1])
-                .filter(|id| self.container(*id).is_some())
+                .filter(|id| self.scene.container(*id).is_some())
         } else {
             None
         };
@@ -733,10 +1169,37 @@ impl SdfBuilderTree {
             // We cannot allow the target location to be "inside" the dragged item, because that would amount moving
             // myself inside of me.
 
-            if self.contains(dragged_item_id, drop_target.target_parent_id) {
+            if self
+                .scene
+                .contains(dragged_item_id, drop_target.target_parent_id)
+            {
                 return;
             }
 
+            // nor can a component end up containing an instance of itself
+            let dragged_item = self
+                .selected_item
+                .new_item
+                .as_ref()
+                .or_else(|| self.scene.item(dragged_item_id));
+            if dragged_item
+                .is_some_and(|item| !self.scene.can_contain(drop_target.target_parent_id, item))
+            {
+                return;
+            }
+
+            // containers with a limited number of children only take new ones when not full
+            if let Some(target) = self.scene.item(drop_target.target_parent_id) {
+                let is_full = target
+                    .capacity()
+                    .is_some_and(|capacity| target.children().map_or(0, Vec::len) >= capacity);
+                if is_full
+                    && self.scene.parent_id(dragged_item_id) != Some(drop_target.target_parent_id)
+                {
+                    return;
+                }
+            }
+
             // extend the cursor to the right of the enclosing container
             let mut span_x = drop_target.indicator_span_x;
             span_x.max = ui.cursor().right();
@@ -797,14 +1260,14 @@ impl SdfBuilderTree {
                     self.send_command(Command::AddItem {
                         item: item.clone(),
                         new_item_id: dragged_item_id,
-                        target_container_id: self.root_id,
+                        target_container_id: self.scene.root_id(),
                         target_position_index: 0,
                     });
                 }
 
                 egui::DragAndDrop::clear_payload(ui.ctx());
             } else {
-                self.send_command(Command::HighlightTargetContainer(self.root_id));
+                self.send_command(Command::HighlightTargetContainer(self.scene.root_id()));
             }
         }
     }
@@ -815,9 +1278,7 @@ impl SdfBuilderTree {
 //
 impl SdfBuilderTree {
     pub fn generate_instructions(&self) -> Vec<Instruction<SdfWrapper<Shape, ItemId>>> {
-        let capacity = self.items.len() + self.extra_item.is_some() as usize;
-        let mut instructions = Vec::with_capacity(capacity);
-        self.generate_instructions_for_id(&self.root_id, &mut instructions);
+        let mut instructions = self.scene.generate_instructions();
         if let (Some((shape, transform)), Some(id)) = (self.extra_item, self.selected_item.id) {
             let instruction = Instruction::Sdf(SdfWrapper::new(shape, id), transform);
             if instructions.is_empty() {
@@ -830,67 +1291,13 @@ impl SdfBuilderTree {
         instructions
     }
 
-    fn generate_instructions_for_id(
-        &self,
-        id: &ItemId,
-        instructions: &mut Vec<Instruction<SdfWrapper<Shape, ItemId>>>,
-    ) -> bool {
-        if let Some(item) = self.items.get(id) {
-            match item {
-                Item::Operator(op, ids) => {
-                    let mut items = ids.iter().rev();
-                    let mut r1 = false;
-                    while !r1 {
-                        if let Some(next_id) = items.next() {
-                            r1 = self.generate_instructions_for_id(next_id, instructions);
-                        } else {
-                            return false;
-                        }
-                    }
-                    let mut r2 = false;
-                    while !r2 {
-                        if let Some(next_id) = items.next() {
-                            r2 = self.generate_instructions_for_id(next_id, instructions);
-                        } else {
-                            return true;
-                        }
-                    }
-                    let op_to_add = if *op == Operator::Subtract {
-                        Operator::Union
-                    } else {
-                        *op
-                    };
-                    instructions.push(Instruction::Operator(op_to_add));
-                    for next_id in items {
-                        if self.generate_instructions_for_id(next_id, instructions) {
-                            instructions.push(Instruction::Operator(op_to_add));
-                        }
-                    }
-                    if *op == Operator::Subtract {
-                        if let Some(Instruction::Operator(op)) = instructions.last_mut() {
-                            *op = Operator::Subtract;
-                        }
-                    }
-                    true
-                }
-                Item::Shape(shape, transform) => {
-                    instructions.push(Instruction::Sdf(SdfWrapper::new(*shape, *id), *transform));
-                    true
-                }
-            }
-        } else {
-            false
-        }
-    }
-
     fn handle_extra_item(&mut self, ui: &egui::Ui, size: PhysicalSize<u32>) {
         let extra_item =
             if !ui.ui_contains_pointer() && egui::DragAndDrop::has_any_payload(ui.ctx()) {
                 if let Some(Item::Shape(shape, _)) = self.selected_item.new_item {
                     ui.input(|i| i.pointer.latest_pos()).map(|pos| {
-                        let transform = Transform {
-                            position: from_pixels(vec2(pos.x, pos.y), size.into()),
-                        };
+                        let transform =
+                            Transform::from_position(from_pixels(vec2(pos.x, pos.y), size.into()));
                         (shape, transform)
                     })
                 } else {
@@ -900,60 +1307,11 @@ impl SdfBuilderTree {
                 None
             };
         if ui.input(|i| i.pointer.primary_released()) {
-            if let Some((shape, transform)) = self.extra_item {
-                let item_id = self.selected_item.id.unwrap();
-                match self.operator_mode {
-                    Operator::Union => {
-                        self.get_root_children_mut().push(item_id);
-                    }
-                    _ => {
-                        let children = self.get_root_children().clone();
-                        if children.is_empty() {
-                            self.get_root_children_mut().push(item_id);
-                        } else {
-                            let container_op_id = ItemId::new();
-                            if children.len() == 1 {
-                                let child = children[0];
-                                let same_op =
-                                    if let Some(Item::Operator(op, _)) = self.items.get(&child) {
-                                        *op == self.operator_mode
-                                    } else {
-                                        false
-                                    };
-                                if same_op {
-                                    if let Some(Item::Operator(_, children)) =
-                                        self.items.get_mut(&child)
-                                    {
-                                        children.push(item_id);
-                                    }
-                                } else {
-                                    self.get_root_children_mut()[0] = container_op_id;
-                                    self.items.insert(
-                                        container_op_id,
-                                        Item::Operator(self.operator_mode, vec![child, item_id]),
-                                    );
-                                }
-                            } else {
-                                let container_union_id = ItemId::new();
-                                self.items.insert(
-                                    container_op_id,
-                                    Item::Operator(
-                                        self.operator_mode,
-                                        vec![container_union_id, item_id],
-                                    ),
-                                );
-                                self.items.insert(
-                                    container_union_id,
-                                    Item::Operator(Operator::Union, children),
-                                );
-                                let children = self.get_root_children_mut();
-                                children.truncate(1);
-                                children[0] = container_op_id;
-                            }
-                        }
-                    }
-                }
-                self.items.insert(item_id, Item::Shape(shape, transform));
+            if let (Some((shape, transform)), Some(item_id)) =
+                (self.extra_item, self.selected_item.id)
+            {
+                self.scene
+                    .combine_item(self.operator_mode, Item::Shape(shape, transform), item_id);
             }
         }
         if extra_item != self.extra_item {
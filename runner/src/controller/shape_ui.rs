@@ -1,5 +1,6 @@
 use dfutils::primitives::*;
 use dfutils::primitives_enum::*;
+use glam::Vec2;
 
 /// The ui for the leaf nodes of [SdfBuilderTree]
 pub trait ShapeUi {
@@ -76,19 +77,60 @@ impl ShapeUi for Cross {
 }
 
 impl ShapeUi for LineSegment {
-    fn ui(self, _ui: &mut egui::Ui) -> Self {
+    fn ui(mut self, ui: &mut egui::Ui) -> Self {
+        ui.label("A");
+        ui.add(egui::DragValue::new(&mut self.a.x).speed(0.01));
+        ui.add(egui::DragValue::new(&mut self.a.y).speed(0.01));
+        ui.end_row();
+        ui.label("B");
+        ui.add(egui::DragValue::new(&mut self.b.x).speed(0.01));
+        ui.add(egui::DragValue::new(&mut self.b.y).speed(0.01));
+        self
+    }
+}
+
+impl ShapeUi for CapsuleChain {
+    fn ui(mut self, ui: &mut egui::Ui) -> Self {
+        let len = self.len as usize;
+        for (i, (point, radius)) in self.points[..len]
+            .iter_mut()
+            .zip(&mut self.radii[..len])
+            .enumerate()
+        {
+            if i > 0 {
+                ui.end_row();
+            }
+            ui.label(format!("Point {}", i + 1));
+            ui.add(egui::DragValue::new(&mut point.x).speed(0.01));
+            ui.add(egui::DragValue::new(&mut point.y).speed(0.01));
+            ui.add(
+                egui::DragValue::new(radius)
+                    .range(0.0..=f64::INFINITY)
+                    .speed(0.01),
+            );
+        }
         self
     }
 }
 
 impl ShapeUi for Plane {
-    fn ui(self, _ui: &mut egui::Ui) -> Self {
+    fn ui(mut self, ui: &mut egui::Ui) -> Self {
+        ui.label("Normal");
+        let mut angle = self.normal.y.atan2(self.normal.x);
+        if ui.drag_angle(&mut angle).changed() {
+            self.normal = Vec2::from_angle(angle);
+        }
         self
     }
 }
 
 impl ShapeUi for Ray {
-    fn ui(self, _ui: &mut egui::Ui) -> Self {
+    fn ui(mut self, ui: &mut egui::Ui) -> Self {
+        ui.label("Direction");
+        let mut angle = self.direction.y.atan2(self.direction.x);
+        if ui.drag_angle(&mut angle).changed() {
+            self.direction = Vec2::from_angle(angle);
+        }
         self
     }
 }
@@ -101,6 +143,7 @@ impl ShapeUi for Shape {
             Shape::Rectangle(shape) => shape.ui(ui).into(),
             Shape::Cross(shape) => shape.ui(ui).into(),
             Shape::LineSegment(shape) => shape.ui(ui).into(),
+            Shape::CapsuleChain(shape) => shape.ui(ui).into(),
             Shape::Plane(shape) => shape.ui(ui).into(),
             Shape::Ray(shape) => shape.ui(ui).into(),
         }
@@ -0,0 +1,147 @@
+use super::scale::Scale;
+use dfutils::primitives_enum::Shape;
+use egui_winit::winit::keyboard::{Key, NamedKey};
+use glam::*;
+use sdf_scene::ItemId;
+use shared::sdf_interpreter::Transform;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TransformKind {
+    Grab,
+    Scale,
+    Rotate,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+/// A modal edit of the selected shape driven by the cursor or by a typed amount
+pub struct TransformMode {
+    pub kind: TransformKind,
+    pub axis: Option<Axis>,
+    pub item_id: ItemId,
+    /// The shape and transform as they were when the mode started
+    pub original: (Shape, Transform),
+    /// Cursor position in world space when the mode started
+    start: Vec2,
+    /// Amount typed so far
+    input: String,
+}
+
+impl TransformMode {
+    pub fn new(
+        kind: TransformKind,
+        item_id: ItemId,
+        shape: Shape,
+        transform: Transform,
+        cursor: Vec2,
+    ) -> Self {
+        Self {
+            kind,
+            axis: None,
+            item_id,
+            original: (shape, transform),
+            start: cursor,
+            input: String::new(),
+        }
+    }
+
+    /// Handle axis constraints and typed amounts, returns whether the key was used
+    pub fn key_input(&mut self, key: &Key) -> bool {
+        match key {
+            Key::Named(NamedKey::Backspace) => {
+                self.input.pop();
+            }
+            Key::Character(c) => match c.as_str() {
+                "x" | "X" => self.toggle_axis(Axis::X),
+                "y" | "Y" => self.toggle_axis(Axis::Y),
+                c if c
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || c == '.' || c == '-') =>
+                {
+                    self.input.push_str(c)
+                }
+                _ => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    fn toggle_axis(&mut self, axis: Axis) {
+        self.axis = if self.axis == Some(axis) {
+            None
+        } else {
+            Some(axis)
+        };
+    }
+
+    fn typed(&self) -> Option<f32> {
+        self.input.parse().ok()
+    }
+
+    /// The shape and transform resulting from the cursor being at `cursor`
+    pub fn apply(&self, cursor: Vec2) -> (Shape, Transform) {
+        let (shape, mut transform) = self.original;
+        let center = transform.position;
+        match self.kind {
+            TransformKind::Grab => {
+                let delta = match (self.typed(), self.axis) {
+                    (Some(v), Some(Axis::Y)) => vec2(0.0, v),
+                    (Some(v), _) => vec2(v, 0.0),
+                    (None, axis) => constrain(cursor - self.start, axis),
+                };
+                transform.position += delta;
+                (shape, transform)
+            }
+            TransformKind::Scale => {
+                let f = self.typed().unwrap_or_else(|| {
+                    let initial = self.start.distance(center);
+                    if initial > 0.0 {
+                        cursor.distance(center) / initial
+                    } else {
+                        1.0
+                    }
+                });
+                let factor = match self.axis {
+                    Some(Axis::X) => vec2(f, 1.0),
+                    Some(Axis::Y) => vec2(1.0, f),
+                    None => Vec2::splat(f),
+                };
+                (shape.scale(factor), transform)
+            }
+            TransformKind::Rotate => {
+                let angle = self.typed().map(f32::to_radians).unwrap_or_else(|| {
+                    let a = self.start - center;
+                    let b = cursor - center;
+                    a.perp_dot(b).atan2(a.dot(b))
+                });
+                transform.rotation += angle;
+                (shape, transform)
+            }
+        }
+    }
+
+    /// Short description of the mode, e.g. "Scale X 1.5"
+    pub fn status(&self) -> String {
+        let mut status = format!("{:?}", self.kind);
+        if let Some(axis) = self.axis {
+            status += &format!(" {axis:?}");
+        }
+        if !self.input.is_empty() {
+            status += &format!(" {}", self.input);
+        }
+        status
+    }
+}
+
+fn constrain(v: Vec2, axis: Option<Axis>) -> Vec2 {
+    match axis {
+        Some(Axis::X) => vec2(v.x, 0.0),
+        Some(Axis::Y) => vec2(0.0, v.y),
+        None => v,
+    }
+}
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 mod app;
@@ -13,7 +14,6 @@ mod ui;
 mod window;
 
 #[derive(StructOpt, Clone, Copy)]
-#[structopt(name = "sdf-builder")]
 pub struct Options {
     // Default to true after the following is fixed
     // https://github.com/gfx-rs/wgpu/issues/5128
@@ -21,8 +21,55 @@ pub struct Options {
     validate_spirv: bool,
 }
 
+#[derive(StructOpt)]
+enum Command {
+    /// Mesh a saved scene extruded along Z, and write it as STL or OBJ
+    ExportMesh {
+        scene: PathBuf,
+        /// Ends in .stl or .obj
+        output: PathBuf,
+        #[structopt(long, default_value = "0.1")]
+        height: f32,
+        #[structopt(long, default_value = "0")]
+        rounding: f32,
+        /// Half the width and height of the meshed area, around the origin
+        #[structopt(long, default_value = "0.5")]
+        extent: f32,
+        #[structopt(long, default_value = "0.002")]
+        cell_size: f32,
+    },
+}
+
+#[derive(StructOpt)]
+#[structopt(name = "sdf-builder")]
+struct Cli {
+    #[structopt(flatten)]
+    options: Options,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
 pub fn main() {
-    let options: Options = Options::from_args();
+    let cli = Cli::from_args();
 
-    app::start(options);
+    match cli.command {
+        Some(Command::ExportMesh {
+            scene,
+            output,
+            height,
+            rounding,
+            extent,
+            cell_size,
+        }) => {
+            let half_size = glam::Vec2::splat(extent);
+            match controller::export_mesh(&scene, &output, height, rounding, half_size, cell_size) {
+                Ok(triangles) => println!("Wrote {triangles} triangles to {}", output.display()),
+                Err(e) => {
+                    eprintln!("Failed to export mesh to {}: {e}", output.display());
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => app::start(cli.options),
+    }
 }
@@ -11,6 +11,7 @@ use egui_winit::winit::{
     dpi::{PhysicalPosition, PhysicalSize},
     event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
     event_loop::EventLoopProxy,
+    keyboard::ModifiersState,
     window::Window,
 };
 
@@ -68,6 +69,14 @@ impl<'a> State<'a> {
         self.controller.keyboard_input(event);
     }
 
+    pub fn captures_escape(&self) -> bool {
+        self.controller.captures_escape()
+    }
+
+    pub fn modifiers_changed(&mut self, modifiers: ModifiersState) {
+        self.controller.modifiers_changed(modifiers);
+    }
+
     pub fn mouse_input(&mut self, state: ElementState, button: MouseButton) {
         self.controller.mouse_input(state, button);
     }
@@ -11,7 +11,9 @@ use egui_winit::{
 pub struct UiState {
     pub fps: usize,
     pub show_fps: bool,
+    pub show_inspector: bool,
     pub vsync: bool,
+    pub scene_path: String,
 }
 
 impl UiState {
@@ -19,7 +21,9 @@ impl UiState {
         Self {
             fps: 0,
             show_fps: true,
+            show_inspector: false,
             vsync: true,
+            scene_path: "scene.json".to_string(),
         }
     }
 }
@@ -82,8 +86,23 @@ impl Ui {
     fn ui(&self, ctx: &Context, ui_state: &mut UiState, controller: &mut Controller) {
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
+                ui.menu_button("File", |ui| {
+                    ui.text_edit_singleline(&mut ui_state.scene_path);
+                    if ui.button("Save").clicked() {
+                        controller.save_scene(&ui_state.scene_path);
+                        ui.close_menu();
+                    }
+                    if ui.button("Open").clicked() {
+                        controller.load_scene(&ui_state.scene_path);
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("View", |ui| {
+                    controller.preview_ui(ui);
+                });
                 ui.menu_button("Settings", |ui| {
                     ui.checkbox(&mut ui_state.show_fps, "fps counter");
+                    ui.checkbox(&mut ui_state.show_inspector, "inspector");
                     if ui.checkbox(&mut ui_state.vsync, "V-Sync").clicked() {
                         self.send_event(UserEvent::SetVSync(ui_state.vsync));
                     }
@@ -96,11 +115,18 @@ impl Ui {
                 }
             });
         });
+        egui::TopBottomPanel::bottom("timeline_panel").show(ctx, |ui| {
+            controller.timeline_ui(ui);
+        });
         egui::SidePanel::right("right_panel")
             .resizable(false)
             .exact_width(162.0)
             .show(ctx, |ui| {
                 controller.ui(ctx, ui, &self.event_proxy);
             });
+        controller.gizmos_ui(ctx);
+        if ui_state.show_inspector {
+            controller.inspector_ui(ctx);
+        }
     }
 }
@@ -1,7 +1,7 @@
 #![cfg_attr(target_arch = "spirv", no_std)]
 
-use dfutils::gridref::*;
-use push_constants::sdf_builder::ShaderConstants;
+use dfutils::{gridref::*, sdf::Sdf, sdf3::*};
+use push_constants::sdf_builder::{preview, ShaderConstants};
 use sdf_wrapper::WrappedDistance;
 use shared::*;
 use spirv_std::glam::*;
@@ -15,6 +15,36 @@ fn sdf(p: Vec2, grid: GridRef<T>) -> T {
     grid.signed_distance(p)
 }
 
+/// The grid as the profile of 3D previews, extended beyond its bounds
+#[derive(Clone, Copy)]
+struct Profile<'a>(GridRef<'a, T>);
+
+impl<'a> Sdf for Profile<'a> {
+    type T = f32;
+    fn signed_distance(&self, p: Vec2) -> f32 {
+        let half_size = vec2(0.5 * self.0.aspect_ratio(), 0.5) - 1e-3;
+        let q = p.clamp(-half_size, half_size);
+        let T { d, .. } = self.0.signed_distance(q);
+        d + p.distance(q)
+    }
+}
+
+fn preview_fs(uv: Vec2, constants: &ShaderConstants, grid: GridRef<T>) -> Vec3 {
+    let camera = Camera::new(
+        constants.camera_yaw,
+        constants.camera_pitch,
+        constants.camera_distance,
+        1.5,
+    );
+    let profile = Profile(grid);
+    if constants.preview == preview::REVOLVE {
+        shade(&Revolve::new(profile, constants.preview_size), &camera, uv)
+    } else {
+        let extruded = Extrude::new(profile, constants.preview_size, constants.rounding);
+        shade(&extruded, &camera, uv)
+    }
+}
+
 #[spirv(fragment)]
 pub fn main_fs(
     #[spirv(frag_coord)] frag_coord: Vec4,
@@ -29,6 +59,10 @@ pub fn main_fs(
         constants.size.height as usize,
         grid_buffer,
     );
+    if constants.preview != preview::FLAT {
+        *output = preview_fs(uv, constants, grid).powf(2.2).extend(1.0);
+        return;
+    }
     let T { d, data: id } = sdf(uv, grid);
     let mut col = if d < 0.0 {
         vec3(0.65, 0.85, 1.0)
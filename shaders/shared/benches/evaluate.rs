@@ -0,0 +1,100 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use dfutils::{primitives::*, primitives_enum::Shape, sdf::Sdf};
+use glam::{vec2, Vec2};
+use shared::{
+    sdf_interpreter::{Instruction, Operator, SdfInstructions, Transform},
+    sdf_wrapper::{SdfWrapper, WrappedDistance},
+};
+use std::hint::black_box;
+
+const SIZE: usize = 256;
+
+/// Pixel centers of a square grid over [-0.5, 0.5]
+fn points() -> Vec<Vec2> {
+    (0..SIZE * SIZE)
+        .map(|i| (vec2((i % SIZE) as f32, (i / SIZE) as f32) + 0.5) / SIZE as f32 - 0.5)
+        .collect()
+}
+
+/// A row of alternating disks and rectangles, minus a thin bar across them
+fn instructions(count: usize) -> Vec<Instruction<SdfWrapper<Shape, u32>>> {
+    let mut instructions = Vec::new();
+    for i in 0..count {
+        let shape = if i % 2 == 0 {
+            Shape::Disk(Disk::new(0.04))
+        } else {
+            Shape::Rectangle(Rectangle::new(0.06, 0.1))
+        };
+        let transform = Transform {
+            position: vec2(i as f32 / count as f32 - 0.5, 0.0),
+            rotation: i as f32,
+        };
+        instructions.push(Instruction::Sdf(SdfWrapper::new(shape, i as u32), transform));
+        if i > 0 {
+            instructions.push(Instruction::Operator(Operator::Union));
+        }
+    }
+    let bar = Shape::Rectangle(Rectangle::new(1.0, 0.01));
+    instructions.push(Instruction::Sdf(
+        SdfWrapper::new(bar, count as u32),
+        Default::default(),
+    ));
+    instructions.push(Instruction::Operator(Operator::Subtract));
+    instructions
+}
+
+fn shapes(c: &mut Criterion) {
+    let points = points();
+    let mut out = vec![0.0; points.len()];
+    let mut group = c.benchmark_group("shapes");
+    for shape in [
+        Shape::Disk(Disk::new(0.3)),
+        Shape::Rectangle(Rectangle::new(0.5, 0.3)),
+        Shape::LineSegment(LineSegment::new(vec2(-0.2, 0.1), vec2(0.3, -0.1))),
+    ] {
+        let name: &str = shape.into();
+        group.bench_with_input(BenchmarkId::new("per_point", name), &shape, |b, shape| {
+            b.iter(|| {
+                for (p, d) in points.iter().zip(&mut out) {
+                    *d = shape.signed_distance(*p);
+                }
+                black_box(&out);
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("batched", name), &shape, |b, shape| {
+            b.iter(|| {
+                shape.signed_distance_many(&points, &mut out);
+                black_box(&out);
+            })
+        });
+    }
+    group.finish();
+}
+
+fn interpreter(c: &mut Criterion) {
+    let points = points();
+    let mut out = vec![WrappedDistance::default(); points.len()];
+    let mut group = c.benchmark_group("interpreter");
+    for count in [4, 16, 64] {
+        let instructions = instructions(count);
+        let sdf = SdfInstructions::new(&instructions);
+        group.bench_with_input(BenchmarkId::new("per_point", count), &sdf, |b, sdf| {
+            b.iter(|| {
+                for (p, d) in points.iter().zip(&mut out) {
+                    *d = sdf.signed_distance(*p);
+                }
+                black_box(&out);
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("batched", count), &sdf, |b, sdf| {
+            b.iter(|| {
+                sdf.signed_distance_many(&points, &mut out);
+                black_box(&out);
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, shapes, interpreter);
+criterion_main!(benches);
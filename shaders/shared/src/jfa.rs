@@ -0,0 +1,33 @@
+//! Jump flooding of masks into approximate distance fields, see [dfutils::jfa]
+//!
+//! Only the CPU runs it for now. The passes are `no_std` so that compute shaders can share them,
+//! but the editor has no mask tools to dispatch them from yet.
+
+pub use dfutils::jfa::*;
+#[cfg(not(target_arch = "spirv"))]
+use spirv_std::glam::{UVec2, Vec2, Vec4};
+
+/// Signed distances in pixels to the contour of `mask`, a grid of `size` pixels row by row
+#[cfg(not(target_arch = "spirv"))]
+pub fn jump_flood(mask: &[bool], size: UVec2) -> Vec<f32> {
+    use rayon::prelude::*;
+
+    let pixel = |i: usize| UVec2::new(i as u32 % size.x, i as u32 / size.x);
+    let mut seeds: Vec<Vec4> = mask
+        .par_iter()
+        .enumerate()
+        .map(|(i, inside)| init(pixel(i), *inside))
+        .collect();
+    for s in steps(size) {
+        seeds = (0..seeds.len())
+            .into_par_iter()
+            .map(|i| step(&seeds, size, pixel(i), s, Vec2::ONE))
+            .collect();
+    }
+    seeds
+        .par_iter()
+        .zip(mask)
+        .enumerate()
+        .map(|(i, (seeds, inside))| distance(*seeds, pixel(i), *inside))
+        .collect()
+}
@@ -1,5 +1,6 @@
 #![cfg_attr(target_arch = "spirv", no_std)]
 
+pub mod jfa;
 pub mod push_constants;
 pub mod sdf_interpreter;
 pub mod sdf_wrapper;
@@ -32,3 +33,78 @@ pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
 pub fn from_pixels(Vec2 { x, y }: Vec2, Size { width, height }: Size) -> Vec2 {
     (vec2(x, -y) - 0.5 * vec2(width as f32, -(height as f32))) / height as f32
 }
+
+pub fn to_pixels(Vec2 { x, y }: Vec2, Size { width, height }: Size) -> Vec2 {
+    vec2(x, -y) * height as f32 + 0.5 * vec2(width as f32, height as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spirv_std::glam::{uvec2, UVec2};
+
+    #[test]
+    fn arc_length_of_the_winner() {
+        use dfutils::{primitives::Disk, sdf::Sdf};
+        use sdf_interpreter::{Instruction, Operator, SdfInstructions, Transform};
+
+        let (a, b) = (Disk::new(0.5), Disk::new(0.3));
+        let offset = Transform::from_position(vec2(0.1, 0.0));
+        let arc_a = |p: Vec2| a.arc_length(p);
+        let arc_b = |p: Vec2| b.arc_length(offset.to_local(p));
+        for (op, p, expected) in [
+            // inside both disks, the xor is the negated distance to the smaller one
+            (Operator::Xor, vec2(0.1, 0.1), arc_b(vec2(0.1, 0.1))),
+            (Operator::Xor, vec2(-0.45, 0.1), arc_a(vec2(-0.45, 0.1))),
+            (Operator::Subtract, vec2(0.1, 0.1), arc_a(vec2(0.1, 0.1))),
+            (Operator::Subtract, vec2(0.6, 0.05), arc_b(vec2(0.6, 0.05))),
+            (Operator::Union, vec2(0.38, 0.05), arc_a(vec2(0.38, 0.05))),
+        ] {
+            let instructions = [
+                Instruction::Sdf(a, Transform::default()),
+                Instruction::Sdf(b, offset),
+                Instruction::Operator(op),
+            ];
+            assert_eq!(SdfInstructions::new(&instructions).arc_length(p), expected);
+        }
+    }
+
+    #[test]
+    fn jump_flooding() {
+        const SIZE: UVec2 = uvec2(97, 61);
+
+        // a disk and a thin bar, in pixels
+        let inside = |x: u32, y: u32| {
+            let p = vec2(x as f32, y as f32);
+            p.distance(vec2(30.0, 25.0)) < 18.0 || (60..90).contains(&x) && (40..43).contains(&y)
+        };
+        let pixels = (0..SIZE.y).flat_map(|y| (0..SIZE.x).map(move |x| (x, y)));
+        let mask: Vec<bool> = pixels.clone().map(|(x, y)| inside(x, y)).collect();
+        let distances = jfa::jump_flood(&mask, SIZE);
+
+        // exact distances to the pixels on the other side of the mask
+        let (inner, outer): (Vec<_>, Vec<_>) = pixels.clone().partition(|(x, y)| inside(*x, *y));
+        let mut exact_pixels = 0;
+        for ((x, y), d) in pixels.zip(&distances) {
+            let p = vec2(x as f32, y as f32);
+            let other_side = if inside(x, y) { &outer } else { &inner };
+            let nearest = other_side
+                .iter()
+                .map(|(qx, qy)| p.distance(vec2(*qx as f32, *qy as f32)))
+                .fold(f32::INFINITY, f32::min);
+            let expected = if inside(x, y) {
+                0.5 - nearest
+            } else {
+                nearest - 0.5
+            };
+            assert!((d - expected).abs() < 0.5, "{d} instead of {expected}");
+            if d == &expected {
+                exact_pixels += 1;
+            }
+        }
+        assert!(exact_pixels as f32 > 0.99 * mask.len() as f32);
+
+        let empty = jfa::jump_flood(&vec![false; mask.len()], SIZE);
+        assert!(empty.iter().all(|d| *d == f32::INFINITY));
+    }
+}
@@ -19,7 +19,7 @@ impl Size {
     }
 }
 
-#[cfg(not(target_arch = "spirv"))]
+#[cfg(all(not(target_arch = "spirv"), feature = "winit"))]
 impl From<winit::dpi::PhysicalSize<u32>> for Size {
     fn from(winit::dpi::PhysicalSize { width, height }: winit::dpi::PhysicalSize<u32>) -> Self {
         Self { width, height }
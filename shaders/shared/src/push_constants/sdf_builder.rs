@@ -1,6 +1,13 @@
 use super::{Bool, Size, Vec2};
 use bytemuck::{Pod, Zeroable};
 
+/// Values of [ShaderConstants::preview]
+pub mod preview {
+    pub const FLAT: u32 = 0;
+    pub const EXTRUDE: u32 = 1;
+    pub const REVOLVE: u32 = 2;
+}
+
 #[derive(Copy, Clone, Pod, Zeroable)]
 #[repr(C)]
 pub struct ShaderConstants {
@@ -9,4 +16,12 @@ pub struct ShaderConstants {
     pub time: f32,
     pub mouse_button_pressed: Bool,
     pub selected_id: u32,
+    /// How the composition is drawn, one of the [preview] constants
+    pub preview: u32,
+    /// Height of the extrusion, or offset from the axis of the revolution
+    pub preview_size: f32,
+    pub rounding: f32,
+    pub camera_yaw: f32,
+    pub camera_pitch: f32,
+    pub camera_distance: f32,
 }
@@ -1,10 +1,31 @@
 use crate::stack::Stack;
-use dfutils::sdf::*;
+#[cfg(not(target_arch = "spirv"))]
+use dfutils::interval::Prune;
+use dfutils::{
+    interval::{Aabb, Interval},
+    lanes::Vec2x4,
+    noise::Noise,
+    outline::Outline,
+    sdf::*,
+};
 use spirv_std::glam::Vec2;
 
+/// Depth of the stack of distances, bounding the nesting of the composition
+const STACK_SIZE: usize = 8;
+
+/// Points evaluated together by [Sdf::signed_distance_many] on instructions
+#[cfg(not(target_arch = "spirv"))]
+const BATCH_SIZE: usize = 64;
+
 #[cfg_attr(
     not(target_arch = "spirv"),
-    derive(Debug, strum::EnumIter, strum::IntoStaticStr)
+    derive(
+        Debug,
+        strum::EnumIter,
+        strum::IntoStaticStr,
+        serde::Serialize,
+        serde::Deserialize
+    )
 )]
 #[derive(Clone, Copy, PartialEq)]
 pub enum Operator {
@@ -15,39 +36,355 @@ pub enum Operator {
 }
 
 impl Operator {
-    fn operate<T>(&self, a: T, b: T) -> T
+    /// The combination of `a` and `b`, along with the operand it comes from, possibly negated
+    fn operate<T>(&self, a: T, b: T) -> (T, Operand)
     where
         T: Copy + SignedDistance,
     {
         use Operator::*;
+        let (da, db) = (a.value(), b.value());
+        let operand = |first: bool| {
+            if first {
+                Operand::First
+            } else {
+                Operand::Second
+            }
+        };
+        match self {
+            Union => (a.union(&b), operand(da < db)),
+            Intersect => (a.intersect(&b), operand(da > db)),
+            Subtract => (a.subtract(&b), operand(-da > db)),
+            Xor => {
+                // the intersection subtracted from the union
+                let first = if -da.max(db) > da.min(db) {
+                    da > db
+                } else {
+                    da < db
+                };
+                (a.xor(&b), operand(first))
+            }
+        }
+    }
+
+    fn interval(&self, a: Interval, b: Interval) -> Interval {
+        use Operator::*;
+        match self {
+            Union => a.union(b),
+            Intersect => a.intersect(b),
+            Subtract => a.subtract(b),
+            Xor => a.xor(b),
+        }
+    }
+
+    /// The operand which the result is everywhere, unchanged, when their distances are within
+    /// `a` and `b`
+    #[cfg(not(target_arch = "spirv"))]
+    fn winner(&self, a: Interval, b: Interval) -> Option<Operand> {
+        use Operator::*;
+        match self {
+            Union if a.max < b.min => Some(Operand::First),
+            Union if b.max <= a.min => Some(Operand::Second),
+            Intersect if a.min > b.max => Some(Operand::First),
+            Intersect if b.min >= a.max => Some(Operand::Second),
+            Subtract if -a.min <= b.min => Some(Operand::Second),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Operand {
+    First,
+    Second,
+}
+
+/// Changes to the distance of a single item, which may depend on the position
+#[cfg_attr(
+    not(target_arch = "spirv"),
+    derive(
+        Debug,
+        strum::EnumIter,
+        strum::IntoStaticStr,
+        serde::Serialize,
+        serde::Deserialize
+    )
+)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Modifier {
+    /// Add noise to the distance, for rough edges
+    Displace(Noise),
+    /// Turn the contour into a band, optionally dashed
+    Outline(Outline),
+}
+
+impl Modifier {
+    /// Modify distance `d` at `p`, in the frame of the leaf it comes from so that the changes
+    /// move along with the items, `arc` being the position along the contour
+    fn apply<T>(&self, d: T, p: Vec2, arc: f32) -> T
+    where
+        T: Copy + SignedDistance,
+    {
+        match self {
+            Modifier::Displace(noise) => d.with_bound(d.value() + noise.sample(p)),
+            Modifier::Outline(outline) => {
+                let band = outline.apply(d.value(), arc);
+                if outline.is_dashed() {
+                    d.with_bound(band)
+                } else {
+                    d.with_new_distance(band)
+                }
+            }
+        }
+    }
+
+    fn interval(&self, d: Interval) -> Interval {
+        match self {
+            // the samples are clamped to the amplitude
+            Modifier::Displace(noise) => d.widen(noise.amplitude.abs()),
+            Modifier::Outline(outline) => {
+                let band = (d - outline.offset).abs() - 0.5 * outline.width;
+                if outline.is_dashed() {
+                    // the gaps are at most their length away from the band
+                    Interval::new(band.min, band.max.max(0.0) + outline.gap)
+                } else {
+                    band
+                }
+            }
+        }
+    }
+
+    fn needs_arc_length(&self) -> bool {
         match self {
-            Union => a.union(&b),
-            Intersect => a.intersect(&b),
-            Subtract => a.subtract(&b),
-            Xor => a.xor(&b),
+            Modifier::Outline(outline) => outline.is_dashed(),
+            _ => false,
         }
     }
 }
 
-#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[cfg_attr(
+    not(target_arch = "spirv"),
+    derive(Debug, serde::Serialize, serde::Deserialize)
+)]
 #[derive(Clone, Copy, Default, PartialEq)]
 pub struct Transform {
     pub position: Vec2,
+    /// Counterclockwise rotation in radians
+    pub rotation: f32,
+}
+
+impl Transform {
+    pub fn from_position(position: Vec2) -> Self {
+        Self {
+            position,
+            rotation: 0.0,
+        }
+    }
+
+    /// Map a point into the space of the transformed item
+    pub fn to_local(&self, p: Vec2) -> Vec2 {
+        Vec2::from_angle(-self.rotation).rotate(p - self.position)
+    }
+
+    /// [Transform::to_local] of four points
+    pub fn to_local_x4(&self, p: Vec2x4) -> Vec2x4 {
+        (p - self.position).rotate(Vec2::from_angle(-self.rotation))
+    }
+
+    /// Bounds of [Transform::to_local] of a box
+    pub fn to_local_aabb(&self, aabb: Aabb) -> Aabb {
+        Aabb::from_points(&aabb.corners().map(|p| self.to_local(p)))
+    }
+
+    /// Map a point from the space of the transformed item
+    pub fn from_local(&self, p: Vec2) -> Vec2 {
+        Vec2::from_angle(self.rotation).rotate(p) + self.position
+    }
+
+    /// The transform placing an item with transform `other` inside the space of `self`
+    pub fn compose(&self, other: &Transform) -> Transform {
+        Self {
+            position: self.from_local(other.position),
+            rotation: self.rotation + other.rotation,
+        }
+    }
 }
 
 pub struct SdfInstructions<'a, U: SignedDistance, T: Copy + Sdf<T = U>> {
     instructions: &'a [Instruction<T>],
+    /// Whether positions along the contours are needed to evaluate the distance
+    needs_arc_length: bool,
 }
 
 impl<'a, U: SignedDistance, T: Copy + Sdf<T = U>> SdfInstructions<'a, U, T> {
     pub fn new(instructions: &'a [Instruction<T>]) -> Self {
-        Self { instructions }
+        let mut needs_arc_length = false;
+        for instruction in instructions {
+            if let Instruction::Modifier(modifier) = instruction {
+                needs_arc_length |= modifier.needs_arc_length();
+            }
+        }
+        Self {
+            instructions,
+            needs_arc_length,
+        }
+    }
+
+    /// Distance at `p`, along with the position along the contour of the winning item if
+    /// `track_arc_length` is set. The distances of the leaves are passed through `leaf`.
+    fn evaluate<V, F>(&self, p: Vec2, track_arc_length: bool, leaf: F) -> (V, f32)
+    where
+        V: SignedDistance,
+        F: Fn(U) -> V,
+    {
+        if self.instructions.is_empty() {
+            return (V::divergent(), 0.0);
+        }
+        let mut stack = Stack::<STACK_SIZE, V>::new();
+        // parallel to `stack`, only used when tracking arc lengths
+        let mut arcs = Stack::<STACK_SIZE, f32>::new();
+        // parallel to `stack`, `p` in the frame of the winning leaf
+        let mut locals = Stack::<STACK_SIZE, Vec2>::new();
+        for instruction in self.instructions {
+            match instruction {
+                Instruction::Operator(op) => {
+                    let b = stack.pop();
+                    let a = stack.pop();
+                    let (d, operand) = op.operate(a, b);
+                    if track_arc_length {
+                        let arc_b = arcs.pop();
+                        let arc_a = arcs.pop();
+                        arcs.push(match operand {
+                            Operand::First => arc_a,
+                            Operand::Second => arc_b,
+                        });
+                    }
+                    let local_b = locals.pop();
+                    let local_a = locals.pop();
+                    locals.push(match operand {
+                        Operand::First => local_a,
+                        Operand::Second => local_b,
+                    });
+                    stack.push(d);
+                }
+                Instruction::Lerp(t) => {
+                    let b = stack.pop();
+                    let a = stack.pop();
+                    if track_arc_length {
+                        let arc_b = arcs.pop();
+                        let arc_a = arcs.pop();
+                        arcs.push(if *t < 0.5 { arc_a } else { arc_b });
+                    }
+                    let local_b = locals.pop();
+                    let local_a = locals.pop();
+                    locals.push(if *t < 0.5 { local_a } else { local_b });
+                    stack.push(a.lerp(&b, *t));
+                }
+                Instruction::Modifier(modifier) => {
+                    let d = stack.pop();
+                    let arc = if track_arc_length { arcs.peek() } else { 0.0 };
+                    stack.push(modifier.apply(d, locals.peek(), arc));
+                }
+                Instruction::Sdf(sdf, transform) => {
+                    let local = transform.to_local(p);
+                    if track_arc_length {
+                        arcs.push(sdf.arc_length(local));
+                    }
+                    locals.push(local);
+                    stack.push(leaf(sdf.signed_distance(local)));
+                }
+            }
+        }
+        (stack.pop(), if track_arc_length { arcs.pop() } else { 0.0 })
+    }
+
+    /// Distance at `p`, along with whether it is exact rather than a lower bound, assuming the
+    /// leaves are exact
+    pub fn signed_distance_tracked(&self, p: Vec2) -> Tracked<U> {
+        self.evaluate(p, self.needs_arc_length, Tracked::exact).0
     }
 }
 
+#[cfg(not(target_arch = "spirv"))]
+impl<'a, U, T> SdfInstructions<'a, U, T>
+where
+    U: SignedDistance,
+    T: Copy + Sdf<T = U>,
+{
+    /// Distances at up to [BATCH_SIZE] points, one instruction at a time over all of them
+    fn evaluate_batch(&self, points: &[Vec2], out: &mut [U]) {
+        let n = points.len();
+        let mut stack = [[U::divergent(); BATCH_SIZE]; STACK_SIZE];
+        // the points in the frame of the winning leaves, parallel to `stack`
+        let mut locals = [[Vec2::ZERO; BATCH_SIZE]; STACK_SIZE];
+        let mut sp = 0;
+        for instruction in self.instructions {
+            match instruction {
+                Instruction::Operator(op) => {
+                    sp -= 1;
+                    let (a, b) = stack.split_at_mut(sp);
+                    let (local_a, local_b) = locals.split_at_mut(sp);
+                    for i in 0..n {
+                        let (d, operand) = op.operate(a[sp - 1][i], b[0][i]);
+                        a[sp - 1][i] = d;
+                        if operand == Operand::Second {
+                            local_a[sp - 1][i] = local_b[0][i];
+                        }
+                    }
+                }
+                Instruction::Lerp(t) => {
+                    sp -= 1;
+                    let (a, b) = stack.split_at_mut(sp);
+                    for (a, b) in a[sp - 1][..n].iter_mut().zip(&b[0][..n]) {
+                        *a = a.lerp(b, *t);
+                    }
+                    if *t >= 0.5 {
+                        locals[sp - 1] = locals[sp];
+                    }
+                }
+                Instruction::Modifier(modifier) => {
+                    for (d, p) in stack[sp - 1][..n].iter_mut().zip(&locals[sp - 1][..n]) {
+                        *d = modifier.apply(*d, *p, 0.0);
+                    }
+                }
+                Instruction::Sdf(sdf, transform) => {
+                    dfutils::lanes::map_points(points, &mut locals[sp][..n], |p| {
+                        transform.to_local_x4(p)
+                    });
+                    sdf.signed_distance_many(&locals[sp][..n], &mut stack[sp][..n]);
+                    sp += 1;
+                }
+            }
+        }
+        out.copy_from_slice(&stack[0][..n]);
+    }
+
+    /// Every leaf whose own distance at `p` is below `threshold`, whether or not it wins the
+    /// composition, ordered by how close `p` is to its boundary.
+    pub fn pick(&self, p: Vec2, threshold: f32) -> Vec<U> {
+        let mut candidates = self
+            .instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Sdf(sdf, transform) => {
+                    Some(sdf.signed_distance(transform.to_local(p)))
+                }
+                Instruction::Operator(_) | Instruction::Lerp(_) | Instruction::Modifier(_) => None,
+            })
+            .filter(|d| d.value() < threshold)
+            .collect::<Vec<_>>();
+        candidates.sort_by(|a, b| a.value().abs().total_cmp(&b.value().abs()));
+        candidates
+    }
+}
+
+#[derive(Clone, Copy)]
 pub enum Instruction<T: Copy> {
     Operator(Operator),
     Sdf(T, Transform),
+    /// Interpolate between the two topmost distances, by the given factor
+    Lerp(f32),
+    /// Modify the topmost distance
+    Modifier(Modifier),
 }
 
 impl<'a, U, T> Sdf for SdfInstructions<'a, U, T>
@@ -57,22 +394,188 @@ where
 {
     type T = U;
     fn signed_distance(&self, p: Vec2) -> U {
+        self.evaluate(p, self.needs_arc_length, |d| d).0
+    }
+
+    /// Evaluates [BATCH_SIZE] points at a time, unless dashed outlines need the positions along
+    /// the contours
+    #[cfg(not(target_arch = "spirv"))]
+    fn signed_distance_many(&self, points: &[Vec2], out: &mut [U]) {
+        if self.instructions.is_empty() || self.needs_arc_length {
+            for (p, d) in points.iter().zip(out) {
+                *d = self.signed_distance(*p);
+            }
+            return;
+        }
+        for (points, out) in points.chunks(BATCH_SIZE).zip(out.chunks_mut(BATCH_SIZE)) {
+            self.evaluate_batch(points, out);
+        }
+    }
+
+    fn arc_length(&self, p: Vec2) -> f32 {
+        self.evaluate(p, true, |d| d).1
+    }
+
+    fn interval(&self, aabb: Aabb) -> Interval {
         if self.instructions.is_empty() {
-            return U::divergent();
+            return Interval::point(f32::INFINITY);
         }
-        let mut stack = Stack::<8, U>::new();
+        let mut stack = Stack::<STACK_SIZE, Interval>::new();
         for instruction in self.instructions {
             match instruction {
                 Instruction::Operator(op) => {
                     let b = stack.pop();
                     let a = stack.pop();
-                    stack.push(op.operate(a, b));
+                    stack.push(op.interval(a, b));
                 }
-                Instruction::Sdf(sdf, Transform { position }) => {
-                    stack.push(sdf.signed_distance(p - *position));
+                Instruction::Lerp(t) => {
+                    let b = stack.pop();
+                    let a = stack.pop();
+                    stack.push(a.lerp(b, *t));
+                }
+                Instruction::Modifier(modifier) => {
+                    let d = stack.pop();
+                    stack.push(modifier.interval(d));
+                }
+                Instruction::Sdf(sdf, transform) => {
+                    stack.push(sdf.interval(transform.to_local_aabb(aabb)));
                 }
             }
         }
         stack.pop()
     }
 }
+
+/// Drops the operands of unions, intersections and subtractions which the result never comes
+/// from inside the box
+#[cfg(not(target_arch = "spirv"))]
+impl<'a, U, T> Prune for SdfInstructions<'a, U, T>
+where
+    U: SignedDistance,
+    T: Copy + Sdf<T = U>,
+{
+    type Pruned = PrunedInstructions<T>;
+
+    fn prune(&self, aabb: Aabb) -> (PrunedInstructions<T>, Interval) {
+        if self.instructions.is_empty() {
+            return (PrunedInstructions::new(Vec::new()), self.interval(aabb));
+        }
+        let mut instructions = Vec::with_capacity(self.instructions.len());
+        // the interval of each operand and where its instructions start
+        let mut stack = Vec::<(Interval, usize)>::with_capacity(STACK_SIZE);
+        for instruction in self.instructions {
+            match instruction {
+                Instruction::Operator(op) => {
+                    let (b, start_b) = stack.pop().unwrap();
+                    let (a, start_a) = stack.pop().unwrap();
+                    match op.winner(a, b) {
+                        Some(Operand::First) => {
+                            instructions.truncate(start_b);
+                            stack.push((a, start_a));
+                        }
+                        Some(Operand::Second) => {
+                            instructions.drain(start_a..start_b);
+                            stack.push((b, start_a));
+                        }
+                        None => {
+                            instructions.push(*instruction);
+                            stack.push((op.interval(a, b), start_a));
+                        }
+                    }
+                }
+                Instruction::Lerp(t) => {
+                    let (b, _) = stack.pop().unwrap();
+                    let (a, start) = stack.pop().unwrap();
+                    instructions.push(*instruction);
+                    stack.push((a.lerp(b, *t), start));
+                }
+                Instruction::Modifier(modifier) => {
+                    let (d, start) = stack.pop().unwrap();
+                    instructions.push(*instruction);
+                    stack.push((modifier.interval(d), start));
+                }
+                Instruction::Sdf(sdf, transform) => {
+                    let start = instructions.len();
+                    instructions.push(*instruction);
+                    stack.push((sdf.interval(transform.to_local_aabb(aabb)), start));
+                }
+            }
+        }
+        let (interval, _) = stack.pop().unwrap();
+        (PrunedInstructions::new(instructions), interval)
+    }
+}
+
+/// Instructions owned, as left by [Prune::prune]
+#[cfg(not(target_arch = "spirv"))]
+pub struct PrunedInstructions<T: Copy> {
+    instructions: Vec<Instruction<T>>,
+    needs_arc_length: bool,
+}
+
+#[cfg(not(target_arch = "spirv"))]
+impl<U, T> PrunedInstructions<T>
+where
+    U: SignedDistance,
+    T: Copy + Sdf<T = U>,
+{
+    fn new(instructions: Vec<Instruction<T>>) -> Self {
+        let needs_arc_length = SdfInstructions::new(&instructions).needs_arc_length;
+        Self {
+            instructions,
+            needs_arc_length,
+        }
+    }
+
+    pub fn as_instructions(&self) -> SdfInstructions<'_, U, T> {
+        SdfInstructions {
+            instructions: &self.instructions,
+            needs_arc_length: self.needs_arc_length,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+}
+
+#[cfg(not(target_arch = "spirv"))]
+impl<U, T> Sdf for PrunedInstructions<T>
+where
+    U: SignedDistance,
+    T: Copy + Sdf<T = U>,
+{
+    type T = U;
+    fn signed_distance(&self, p: Vec2) -> U {
+        self.as_instructions().signed_distance(p)
+    }
+
+    fn signed_distance_many(&self, points: &[Vec2], out: &mut [U]) {
+        self.as_instructions().signed_distance_many(points, out);
+    }
+
+    fn arc_length(&self, p: Vec2) -> f32 {
+        self.as_instructions().arc_length(p)
+    }
+
+    fn interval(&self, aabb: Aabb) -> Interval {
+        self.as_instructions().interval(aabb)
+    }
+}
+
+#[cfg(not(target_arch = "spirv"))]
+impl<U, T> Prune for PrunedInstructions<T>
+where
+    U: SignedDistance,
+    T: Copy + Sdf<T = U>,
+{
+    type Pruned = Self;
+
+    fn prune(&self, aabb: Aabb) -> (Self, Interval) {
+        self.as_instructions().prune(aabb)
+    }
+}
@@ -1,4 +1,7 @@
-use dfutils::sdf::*;
+use dfutils::{
+    interval::{Aabb, Interval},
+    sdf::*,
+};
 use spirv_std::glam::*;
 
 #[derive(Clone, Copy)]
@@ -29,9 +32,32 @@ where
         WrappedDistance::new(self.sdf.signed_distance(p), self.data)
     }
 
+    #[cfg(not(target_arch = "spirv"))]
+    fn signed_distance_many(&self, points: &[Vec2], out: &mut [WrappedDistance<T>]) {
+        let mut distances = [0.0; 64];
+        for (points, out) in points
+            .chunks(distances.len())
+            .zip(out.chunks_mut(distances.len()))
+        {
+            let distances = &mut distances[..points.len()];
+            self.sdf.signed_distance_many(points, distances);
+            for (out, d) in out.iter_mut().zip(distances) {
+                *out = WrappedDistance::new(*d, self.data);
+            }
+        }
+    }
+
     fn distance(&self, p: Vec2) -> WrappedDistance<T> {
         WrappedDistance::new(self.sdf.distance(p), self.data)
     }
+
+    fn arc_length(&self, p: Vec2) -> f32 {
+        self.sdf.arc_length(p)
+    }
+
+    fn interval(&self, aabb: Aabb) -> Interval {
+        self.sdf.interval(aabb)
+    }
 }
 
 #[repr(C, packed)]
@@ -60,3 +86,11 @@ impl<T: Copy + Default> SignedDistance for WrappedDistance<T> {
         Self::new(f32::INFINITY, Default::default())
     }
 }
+
+impl<T: Copy + Default> WithData for WrappedDistance<T> {
+    type Data = T;
+
+    fn data(&self) -> T {
+        self.data
+    }
+}